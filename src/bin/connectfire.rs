@@ -3,8 +3,8 @@ use clap::Parser;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use log::{error, info, warn};
 use satfire::{
-    BoundingBox, ClusterDatabase, Coord, Fire, FireList, FireListUpdateResult, FireListView,
-    FiresDatabase, SatFireResult, Satellite,
+    AppResult, BoundingBox, ClusterDatabase, Coord, Durability, Fire, FireList,
+    FireListUpdateResult, FireListView, FiresDatabase, Satellite,
 };
 use simple_logger::SimpleLogger;
 use std::{
@@ -70,13 +70,21 @@ struct ConnectFireOptions {
     #[clap(env = "FIRES_DB")]
     fires_store_file: PathBuf,
 
+    /// Sync every commit to disk before moving on.
+    ///
+    /// By default the database may lose its most recent commits after a power failure or an
+    /// operating system crash, which only means running the program again. This is slower, but
+    /// nothing that was committed is ever lost.
+    #[clap(long)]
+    full_sync: bool,
+
     /// Verbose output
     #[clap(short, long)]
     verbose: bool,
 }
 
 /// Parse a bounding box argument.
-fn parse_bbox(bbox_str: &str) -> SatFireResult<BoundingBox> {
+fn parse_bbox(bbox_str: &str) -> AppResult<BoundingBox> {
     let corners: Vec<_> = bbox_str.split(',').collect();
 
     if corners.len() < 4 {
@@ -123,7 +131,7 @@ fn parse_bbox(bbox_str: &str) -> SatFireResult<BoundingBox> {
 }
 
 /// Parse a command line datetime
-fn parse_datetime(dt_str: &str) -> SatFireResult<DateTime<Utc>> {
+fn parse_datetime(dt_str: &str) -> AppResult<DateTime<Utc>> {
     const TIME_FORMAT: &str = "%Y-%m-%d-%H:%M:%S";
     let t_str = format!("{}:00:00", dt_str);
 
@@ -149,7 +157,7 @@ impl Display for ConnectFireOptions {
 /// Get the command line arguments and check them.
 ///
 /// If there is missing data, try to fill it in with environment variables.
-fn parse_args() -> SatFireResult<ConnectFireOptions> {
+fn parse_args() -> AppResult<ConnectFireOptions> {
     let opts = ConnectFireOptions::parse();
 
     if opts.verbose {
@@ -290,6 +298,7 @@ impl FireStats {
 /*-------------------------------------------------------------------------------------------------
  *                                   Processing For A Satellite
  *-----------------------------------------------------------------------------------------------*/
+#[allow(clippy::too_many_arguments)]
 fn process_rows_for_satellite<P1: AsRef<Path>, P2: AsRef<Path>, P3: AsRef<Path>>(
     fires_db_store: P1,
     clusters_db_store: P2,
@@ -300,7 +309,7 @@ fn process_rows_for_satellite<P1: AsRef<Path>, P2: AsRef<Path>, P3: AsRef<Path>>
     kmz_path: P3,
     to_db_filler: Sender<DatabaseMessage>,
     verbose: bool,
-) -> SatFireResult<()> {
+) -> AppResult<()> {
     let db = FiresDatabase::connect(fires_db_store.as_ref())?;
 
     let mut current_fires = db.ongoing_fires(sat)?;
@@ -324,7 +333,7 @@ fn process_rows_for_satellite<P1: AsRef<Path>, P2: AsRef<Path>, P3: AsRef<Path>>
         }
         (None, None) => sat.operational(),
     };
-    let end = end.unwrap_or_else(|| Utc::now());
+    let end = end.unwrap_or_else(Utc::now);
 
     drop(db);
 
@@ -341,7 +350,7 @@ fn process_rows_for_satellite<P1: AsRef<Path>, P2: AsRef<Path>, P3: AsRef<Path>>
     let db = ClusterDatabase::connect(clusters_db_store.as_ref())?;
     let mut stats = FireStats::new(sat);
 
-    let mut rows = db.query_clusters(Some(sat), None, start, end, area)?;
+    let mut rows = db.query_clusters(Some(sat), None, start, end, area, None)?;
     let rows = rows.rows()?;
 
     let mut current_time_step: DateTime<Utc> =
@@ -369,7 +378,7 @@ fn process_rows_for_satellite<P1: AsRef<Path>, P2: AsRef<Path>, P3: AsRef<Path>>
                 Some(None)
             }
         })
-        .filter_map(|val| val)
+        .flatten()
     {
         if group_time - last_merge > Duration::hours(1) {
             // Only merge once per hour to speed things up.
@@ -492,9 +501,11 @@ enum DatabaseMessage {
 fn database_filler(
     db_store: PathBuf,
     messages: Receiver<DatabaseMessage>,
-) -> JoinHandle<SatFireResult<()>> {
+    durability: Durability,
+) -> JoinHandle<AppResult<()>> {
     thread::spawn(move || {
         let db = FiresDatabase::connect(db_store)?;
+        db.set_durability(durability)?;
         let mut add_fire = db.prepare_to_add_fires()?;
 
         for message in messages {
@@ -515,9 +526,18 @@ fn database_filler(
  *-----------------------------------------------------------------------------------------------*/
 fn register_signal_handlers() {
     unsafe {
-        libc::signal(libc::SIGTERM, handle_shutdown_signal as usize);
-        libc::signal(libc::SIGQUIT, handle_shutdown_signal as usize);
-        libc::signal(libc::SIGINT, handle_shutdown_signal as usize);
+        libc::signal(
+            libc::SIGTERM,
+            handle_shutdown_signal as fn(libc::c_int) as usize,
+        );
+        libc::signal(
+            libc::SIGQUIT,
+            handle_shutdown_signal as fn(libc::c_int) as usize,
+        );
+        libc::signal(
+            libc::SIGINT,
+            handle_shutdown_signal as fn(libc::c_int) as usize,
+        );
     }
 }
 
@@ -530,7 +550,7 @@ fn handle_shutdown_signal(_signal: libc::c_int) {
 /*-------------------------------------------------------------------------------------------------
  *                                             Main
  *-----------------------------------------------------------------------------------------------*/
-fn main() -> SatFireResult<()> {
+fn main() -> AppResult<()> {
     register_signal_handlers();
 
     SimpleLogger::new().init()?;
@@ -582,7 +602,12 @@ fn main() -> SatFireResult<()> {
     }
     drop(send_to_db_filler);
 
-    let jh_db_filler = database_filler(opts.fires_store_file, from_processing);
+    let durability = if opts.full_sync {
+        Durability::Full
+    } else {
+        Durability::Normal
+    };
+    let jh_db_filler = database_filler(opts.fires_store_file, from_processing, durability);
 
     jh_db_filler
         .join()
@@ -1,7 +1,8 @@
 use clap::Parser;
-use log::info;
+use log::{info, warn};
 use satfire::{
-    BoundingBox, ClusterDatabase, Coord, Geo, KmlWriter, KmzFile, SatFireResult, Satellite, Sector,
+    AppResult, BoundingBox, ClusterDatabase, Coord, CoverageEntry, Geo, KmlWriter, KmzFile,
+    Satellite, Sector,
 };
 use simple_logger::SimpleLogger;
 use std::{
@@ -40,8 +41,8 @@ struct CurrentClustersOptionsInit {
 
     /// The satellite to export the data for.
     ///
-    /// If this is not specified, then it will default to GOES-17. Allowed values are G16 and G17.
-    #[clap(parse(try_from_str=parse_satellite))]
+    /// If this is not specified, then it will default to GOES-17. Allowed values are G16, G17,
+    /// and G18.
     #[clap(default_value_t=Satellite::G17)]
     sat: Satellite,
 
@@ -49,27 +50,27 @@ struct CurrentClustersOptionsInit {
     ///
     /// If this is not specified, then it will default to full disk. Allowed values are FDCF (for
     /// full disk), FDCC (for CONUS), FDCM1 (for meso-sector 1), and FDCM2 (for meso-sector 2).
-    #[clap(parse(try_from_str=parse_sector))]
     #[clap(default_value = "FDCF")]
     sector: Sector,
 
+    /// Only export clusters with at least this much power (MW).
+    ///
+    /// If this is not specified, then all clusters are exported.
+    #[clap(short, long, parse(try_from_str=parse_min_power))]
+    min_power: Option<f64>,
+
+    /// The size of the power labels on the clusters, 1.0 is the normal size.
+    ///
+    /// If this is not specified, then the labels are hidden (0.0), because at full disk zoom there
+    /// are too many of them to read.
+    #[clap(short, long, default_value_t = 0.0, parse(try_from_str=parse_label_scale))]
+    label_scale: f64,
+
     /// Verbose output
     #[clap(short, long)]
     verbose: bool,
 }
 
-fn parse_satellite(sat: &str) -> SatFireResult<Satellite> {
-    let sat = Satellite::string_contains_satellite(sat)
-        .ok_or_else(|| format!("Argument is not a valid satellite name: {}", sat))?;
-    Ok(sat)
-}
-
-fn parse_sector(sector: &str) -> SatFireResult<Sector> {
-    let sector = Sector::string_contains_sector(sector)
-        .ok_or_else(|| format!("Argument is not a valid sector name: {}", sector))?;
-    Ok(sector)
-}
-
 #[derive(Debug)]
 struct CurrentClustersOptionsChecked {
     /// The path to the database file.
@@ -84,6 +85,12 @@ struct CurrentClustersOptionsChecked {
     /// The Sector.
     sector: Sector,
 
+    /// The minimum power (MW) of a cluster to export.
+    min_power: Option<f64>,
+
+    /// The size of the cluster labels, 0.0 hides them.
+    label_scale: f64,
+
     /// Verbose output
     verbose: bool,
 }
@@ -95,21 +102,53 @@ impl Display for CurrentClustersOptionsChecked {
         writeln!(f, "  Output KMZ: {}", self.kmz_file.display())?;
         writeln!(f, "   Satellite: {}", self.sat.name())?;
         writeln!(f, "      Sector: {}", self.sector.name())?;
+        if let Some(min_power) = self.min_power {
+            writeln!(f, "   Min Power: {:.0}MW", min_power)?;
+        }
+        writeln!(f, " Label Scale: {}", self.label_scale)?;
         writeln!(f, "\n")?; // yes, two blank lines.
 
         Ok(())
     }
 }
 
+/// Parse a minimum power argument.
+fn parse_min_power(power_str: &str) -> AppResult<f64> {
+    let min_power: f64 = power_str.parse()?;
+
+    if !min_power.is_finite() {
+        return Err(format!("Minimum power must be a finite number: {}", min_power).into());
+    }
+
+    Ok(min_power)
+}
+
+/// Parse a label scale argument.
+fn parse_label_scale(scale_str: &str) -> AppResult<f64> {
+    let label_scale: f64 = scale_str.parse()?;
+
+    if !label_scale.is_finite() || label_scale < 0.0 {
+        return Err(format!(
+            "Label scale must be a finite number that isn't negative: {}",
+            label_scale
+        )
+        .into());
+    }
+
+    Ok(label_scale)
+}
+
 /// Get the command line arguments and check them.
 ///
 /// If there is missing data, try to fill it in with environment variables.
-fn parse_args() -> SatFireResult<CurrentClustersOptionsChecked> {
+fn parse_args() -> AppResult<CurrentClustersOptionsChecked> {
     let CurrentClustersOptionsInit {
         cluster_store_file,
         kmz_file,
         sat,
         sector,
+        min_power,
+        label_scale,
         verbose,
     } = CurrentClustersOptionsInit::parse();
 
@@ -127,6 +166,8 @@ fn parse_args() -> SatFireResult<CurrentClustersOptionsChecked> {
         kmz_file,
         sat,
         sector,
+        min_power,
+        label_scale,
         verbose,
     };
 
@@ -137,10 +178,22 @@ fn parse_args() -> SatFireResult<CurrentClustersOptionsChecked> {
     Ok(checked)
 }
 
+/// Log what is available in the database.
+fn log_coverage(coverage: &[CoverageEntry]) {
+    if coverage.is_empty() {
+        info!("The database has no clusters.");
+    } else {
+        info!("Available data:");
+        for entry in coverage {
+            info!("    {}", entry);
+        }
+    }
+}
+
 /*-------------------------------------------------------------------------------------------------
  *                                             MAIN
  *-----------------------------------------------------------------------------------------------*/
-fn main() -> SatFireResult<()> {
+fn main() -> AppResult<()> {
     SimpleLogger::new().init()?;
 
     let opts = parse_args()?;
@@ -149,6 +202,21 @@ fn main() -> SatFireResult<()> {
     // Load the data, the most recent clusters.
     //
     let db = ClusterDatabase::connect(&opts.cluster_store_file)?;
+
+    let coverage = db.coverage_summary()?;
+    if !coverage
+        .iter()
+        .any(|entry| entry.satellite == opts.sat && entry.sector == opts.sector)
+    {
+        warn!(
+            "No clusters for {} {} in the database.",
+            opts.sat.name(),
+            opts.sector.name()
+        );
+        log_coverage(&coverage);
+        return Ok(());
+    }
+
     let latest = db.newest_scan_start(opts.sat, opts.sector)?;
     let latest_start = latest - chrono::Duration::seconds(1);
     let latest_end = latest + chrono::Duration::hours(1);
@@ -172,6 +240,7 @@ fn main() -> SatFireResult<()> {
             latest_start,
             latest_end,
             region,
+            opts.min_power,
         )?
         .rows()?
         .filter_map(|res| res.ok())
@@ -195,6 +264,7 @@ fn main() -> SatFireResult<()> {
         Some("http://maps.google.com/mapfiles/kml/shapes/firedept.png"),
         1.3,
     )?;
+    kfile.create_label_style(Some("FFFFFFFF"), opts.label_scale)?;
     kfile.finish_style()?;
 
     kfile.start_folder(Some(opts.sat.name()), None, false)?;
@@ -222,7 +292,7 @@ fn main() -> SatFireResult<()> {
         kfile.start_placemark(None, Some(&description), Some("#fire"))?;
         kfile.create_point(centroid.lat, centroid.lon, 0.0)?;
         kfile.finish_placemark()?;
-        cluster.pixels.kml_write(&mut kfile);
+        cluster.pixels.kml_write(&mut kfile, None);
         kfile.finish_folder()?;
     }
 
@@ -1,7 +1,7 @@
 use chrono::Duration;
 use clap::Parser;
 use log::info;
-use satfire::{FireList, FiresDatabase, SatFireResult, Satellite};
+use satfire::{AppResult, FireList, FiresDatabase, Satellite};
 use simple_logger::SimpleLogger;
 use std::{
     cmp::Reverse,
@@ -40,8 +40,8 @@ struct CurrentFiresOptionsInit {
 
     /// The satellite to export the data for.
     ///
-    /// If this is not specified, then it will default to GOES-17. Allowed values are G16 and G17.
-    #[clap(parse(try_from_str=parse_satellite))]
+    /// If this is not specified, then it will default to GOES-17. Allowed values are G16, G17,
+    /// and G18.
     #[clap(default_value_t=Satellite::G17)]
     sat: Satellite,
 
@@ -50,12 +50,6 @@ struct CurrentFiresOptionsInit {
     verbose: bool,
 }
 
-fn parse_satellite(sat: &str) -> SatFireResult<Satellite> {
-    let sat = Satellite::string_contains_satellite(sat)
-        .ok_or_else(|| format!("Argument is not a valid satellite name: {}", sat))?;
-    Ok(sat)
-}
-
 #[derive(Debug)]
 struct CurrentFiresOptionsChecked {
     /// The path to the database file.
@@ -86,7 +80,7 @@ impl Display for CurrentFiresOptionsChecked {
 /// Get the command line arguments and check them.
 ///
 /// If there is missing data, try to fill it in with environment variables.
-fn parse_args() -> SatFireResult<CurrentFiresOptionsChecked> {
+fn parse_args() -> AppResult<CurrentFiresOptionsChecked> {
     let CurrentFiresOptionsInit {
         fires_store_file,
         kmz_file,
@@ -120,7 +114,7 @@ fn parse_args() -> SatFireResult<CurrentFiresOptionsChecked> {
 /*-------------------------------------------------------------------------------------------------
  *                                             MAIN
  *-----------------------------------------------------------------------------------------------*/
-fn main() -> SatFireResult<()> {
+fn main() -> AppResult<()> {
     SimpleLogger::new().init()?;
 
     let opts = parse_args()?;
@@ -5,13 +5,15 @@ use clap::Parser;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use log::{debug, info, warn};
 use satfire::{
-    Cluster, ClusterDatabase, ClusterList, Geo, KmlWriter, KmzFile, SatFireResult, Satellite,
-    Sector,
+    json_number, AppResult, Cluster, ClusterDatabase, ClusterList, ClusterListError,
+    ClusterListOptions, Durability, Geo, ImageBuffers, KmlWriter, KmzFile, Satellite, Sector,
 };
 use simple_logger::SimpleLogger;
 use std::{
     collections::HashMap,
     fmt::{self, Display, Formatter},
+    fs::File,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
     thread::JoinHandle,
 };
@@ -40,7 +42,8 @@ use strum::IntoEnumIterator;
 ///
 /// At the end of processing, some summary statistics are printed to the screen and a file called
 /// findfire.kmz is output in the same location as the database file findfire.sqlite that has some
-/// summary statistics about the clusters and images that were analyzed during this run.
+/// summary statistics about the clusters and images that were analyzed during this run. The same
+/// statistics can optionally be saved in a JSON file next to the KMZ file.
 ///
 #[derive(Debug, Parser)]
 #[clap(bin_name = "findfire")]
@@ -73,6 +76,29 @@ struct FindFireOptionsInit {
     #[clap(short, long)]
     new_only: bool,
 
+    /// The maximum scan angle in degrees for a cluster to be kept.
+    ///
+    /// Clusters with a pixel at or beyond this scan angle are not saved in the database or used
+    /// in the summary statistics. There are a lot of outliers near the limb of the Earth as
+    /// viewed by the satellite, and this is a QC tool to remove them.
+    #[clap(long, parse(try_from_str=parse_max_scan_angle))]
+    #[clap(default_value_t=DEFAULT_MAX_SCAN_ANGLE)]
+    max_scan_angle: f64,
+
+    /// Also save the summary statistics in a JSON file.
+    ///
+    /// The file is created next to the KMZ file by replacing its extension with "*.json".
+    #[clap(short, long)]
+    json: bool,
+
+    /// Sync every commit to disk before moving on.
+    ///
+    /// By default the database may lose its most recent commits after a power failure or an
+    /// operating system crash, which only means running the program again. This is slower, but
+    /// nothing that was committed is ever lost.
+    #[clap(long)]
+    full_sync: bool,
+
     /// Verbose output
     #[clap(short, long)]
     verbose: bool,
@@ -86,25 +112,52 @@ struct FindFireOptionsChecked {
     /// The path to a KMZ file to produce from this run.
     kmz_file: PathBuf,
 
+    /// The path to a JSON file with the summary statistics to produce from this run.
+    json_file: Option<PathBuf>,
+
     /// The path to the data directory that will be walked to find new data.
     data_dir: PathBuf,
 
     /// Only look for data newer than the most recent in the database.
     new_only: bool,
 
+    /// The maximum scan angle in degrees for a cluster to be kept.
+    max_scan_angle: f64,
+
+    /// How hard the database works to make sure commits survive a crash.
+    durability: Durability,
+
     /// Verbose output
     verbose: bool,
 }
 
+/// Parse a maximum scan angle argument.
+fn parse_max_scan_angle(angle_str: &str) -> AppResult<f64> {
+    let max_scan_angle: f64 = angle_str.parse()?;
+
+    if max_scan_angle.is_nan() || max_scan_angle < 0.0 {
+        return Err(format!(
+            "Maximum scan angle must be a number that isn't negative: {}",
+            max_scan_angle
+        )
+        .into());
+    }
+
+    Ok(max_scan_angle)
+}
+
 /// Get the command line arguments and check them.
 ///
 /// If there is missing data, try to fill it in with environment variables.
-fn parse_args() -> SatFireResult<FindFireOptionsChecked> {
+fn parse_args() -> AppResult<FindFireOptionsChecked> {
     let FindFireOptionsInit {
         cluster_store_file,
         kmz_file,
         data_dir,
         new_only,
+        max_scan_angle,
+        json,
+        full_sync,
         verbose,
     } = FindFireOptionsInit::parse();
 
@@ -117,11 +170,28 @@ fn parse_args() -> SatFireResult<FindFireOptionsChecked> {
         }
     };
 
+    let json_file = if json {
+        let mut clone = kmz_file.clone();
+        clone.set_extension("json");
+        Some(clone)
+    } else {
+        None
+    };
+
+    let durability = if full_sync {
+        Durability::Full
+    } else {
+        Durability::Normal
+    };
+
     Ok(FindFireOptionsChecked {
         cluster_store_file,
         kmz_file,
+        json_file,
         data_dir,
         new_only,
+        max_scan_angle,
+        durability,
         verbose,
     })
 }
@@ -131,7 +201,7 @@ fn parse_args() -> SatFireResult<FindFireOptionsChecked> {
  *-----------------------------------------------------------------------------------------------*/
 const NUM_LOADER_THREADS: u8 = 4;
 
-fn main() -> SatFireResult<()> {
+fn main() -> AppResult<()> {
     SimpleLogger::new().init()?;
 
     let opts = parse_args()?;
@@ -150,14 +220,18 @@ fn main() -> SatFireResult<()> {
     let store_file = &opts.cluster_store_file;
     let verbose = opts.verbose;
     let only_new = opts.new_only;
+    let max_scan_angle = opts.max_scan_angle;
 
     let walk_dir = dir_walker(data_dir, store_file, to_present_filter, only_new, verbose)?;
     let filter_present = filter_already_processed(store_file, from_dir_walker, to_loader, verbose)?;
-    let loader = loader_threads(from_present_filter, to_db_writer, verbose)?;
+    let loader = loader_threads(from_present_filter, to_db_writer, max_scan_angle, verbose)?;
     let db_filler = db_filler_thread(
         &opts.cluster_store_file,
         from_loader,
         &opts.kmz_file,
+        opts.json_file.clone(),
+        max_scan_angle,
+        opts.durability,
         opts.verbose,
     )?;
 
@@ -184,7 +258,7 @@ fn dir_walker<P: AsRef<Path>>(
     to_db_present_filter: Sender<PathBuf>,
     only_new: bool,
     verbose: bool,
-) -> SatFireResult<JoinHandle<SatFireResult<()>>> {
+) -> AppResult<JoinHandle<AppResult<()>>> {
     let data_dir = data_dir.as_ref().to_path_buf();
 
     // Get the most recent version in the database if necessary
@@ -246,7 +320,7 @@ fn filter_already_processed<P: AsRef<Path>>(
     from_dir_walker: Receiver<PathBuf>,
     to_loader: Sender<PathBuf>,
     verbose: bool,
-) -> SatFireResult<Vec<JoinHandle<SatFireResult<()>>>> {
+) -> AppResult<Vec<JoinHandle<AppResult<()>>>> {
     let store_file = store_file.as_ref().to_path_buf();
 
     let mut handles = Vec::with_capacity(num_cpus::get());
@@ -290,8 +364,9 @@ fn filter_already_processed<P: AsRef<Path>>(
 fn loader_threads(
     from_db_present_filter: Receiver<PathBuf>,
     to_db_writer: Sender<ClusterList>,
+    max_scan_angle: f64,
     verbose: bool,
-) -> SatFireResult<Vec<JoinHandle<SatFireResult<()>>>> {
+) -> AppResult<Vec<JoinHandle<AppResult<()>>>> {
     let mut jhs = Vec::with_capacity(NUM_LOADER_THREADS as usize);
 
     for _ in 0..NUM_LOADER_THREADS {
@@ -301,19 +376,26 @@ fn loader_threads(
         let jh = std::thread::Builder::new()
             .name("findfire-load".to_owned())
             .spawn(move || {
+                let mut buffers = ImageBuffers::new();
+
                 for path in from_db_present {
-                    let mut clist = match ClusterList::from_file(&path) {
+                    let options = ClusterListOptions::new().buffers(&mut buffers);
+                    let mut clist = match ClusterList::from_file(&path, options) {
                         Ok(clist) => clist,
-                        Err(err) => {
+                        Err(err @ ClusterListError::UnrecognizedFile(_)) => {
                             if verbose {
-                                warn!(target: "loading", "({}) {}", err, path.display());
+                                info!(target: "loading", "skipping ({}) {}", err, path.display());
                             }
 
                             continue;
                         }
+                        Err(err @ ClusterListError::ReadFailure(_)) => {
+                            warn!(target: "loading", "({}) {}", err, path.display());
+                            continue;
+                        }
                     };
 
-                    clist.filter(is_cluster_a_keeper);
+                    clist.filter(|cluster| is_cluster_a_keeper(cluster, max_scan_angle));
 
                     to_db_writer.send(clist)?;
                 }
@@ -331,8 +413,11 @@ fn db_filler_thread<P: AsRef<Path>>(
     store_file: P,
     from_loader: Receiver<ClusterList>,
     kmz_path: P,
+    json_path: Option<PathBuf>,
+    max_scan_angle: f64,
+    durability: Durability,
     verbose: bool,
-) -> SatFireResult<JoinHandle<SatFireResult<()>>> {
+) -> AppResult<JoinHandle<AppResult<()>>> {
     let store_file = store_file.as_ref().to_path_buf();
     let kmz_path = kmz_path.as_ref().to_path_buf();
 
@@ -340,13 +425,14 @@ fn db_filler_thread<P: AsRef<Path>>(
         .name("findfire-dbase".to_owned())
         .spawn(move || {
             let db = ClusterDatabase::connect(store_file)?;
+            db.set_durability(durability)?;
             let mut add_stmt = db.prepare_to_add_clusters()?;
 
             let mut cluster_stats: Option<ClusterStats> = None;
             let mut cluster_list_stats: Option<ClusterListStats> = None;
 
             for cluster_list in from_loader {
-                ClusterStats::update(&mut cluster_stats, &cluster_list);
+                ClusterStats::update(&mut cluster_stats, &cluster_list, max_scan_angle);
                 ClusterListStats::update(&mut cluster_list_stats, &cluster_list);
                 add_stmt.add(cluster_list)?;
             }
@@ -355,6 +441,9 @@ fn db_filler_thread<P: AsRef<Path>>(
                 (cluster_stats, cluster_list_stats)
             {
                 save_cluster_stats_kmz(kmz_path, cluster_stats)?;
+                if let Some(json_path) = json_path {
+                    save_stats_json(json_path, cluster_stats, cluster_list_stats)?;
+                }
                 if verbose {
                     info!(target: "stats", "{}", cluster_stats);
                     info!(target: "stats", "{}", cluster_list_stats);
@@ -371,16 +460,17 @@ fn db_filler_thread<P: AsRef<Path>>(
  *                             Cluster and Image Statistics
  *-----------------------------------------------------------------------------------------------*/
 
-/* Use this as the maximum value of the scan angle allowed for a cluster to be considered in the
- * summary statistics. This is a QC tool, there are a lot of outliers on the limb of the Earth as
- * viewed by the GOES satellites, and the angles / geometry seem to have something to do with it.
+/* Use this as the default maximum value of the scan angle allowed for a cluster to be kept and
+ * considered in the summary statistics. This is a QC tool, there are a lot of outliers on the limb
+ * of the Earth as viewed by the GOES satellites, and the angles / geometry seem to have something
+ * to do with it.
  *
  * The value of 8.3 degrees is based on visual inspection of a graph of cluster power vs max scan
  * angle of the cluster member centroids. Based on the satellite product documentation
  * (https://www.goes-r.gov/products/docs/PUG-L2+-vol5.pdf) I calculated that the limb of the Earth
  * is at a scan angle of about 8.7 degrees.
  */
-const MAX_SCAN_ANGLE: f64 = 8.3;
+const DEFAULT_MAX_SCAN_ANGLE: f64 = 8.3;
 
 #[derive(Debug, Clone)]
 struct ClusterStat {
@@ -450,15 +540,35 @@ struct ClusterStats {
 }
 
 impl ClusterStats {
-    fn update(stats: &mut Option<Self>, clusters: &ClusterList) {
-        let sat = clusters.satellite();
-        let sector = clusters.sector();
-        let start = clusters.scan_start();
-        let end = clusters.scan_end();
-
-        for cluster in clusters.clusters() {
-            if cluster.max_scan_angle() >= MAX_SCAN_ANGLE {
-                return;
+    fn update(stats: &mut Option<Self>, clusters: &ClusterList, max_scan_angle: f64) {
+        Self::update_with_clusters(
+            stats,
+            clusters.clusters(),
+            clusters.satellite(),
+            clusters.sector(),
+            clusters.scan_start(),
+            clusters.scan_end(),
+            max_scan_angle,
+        )
+    }
+
+    fn update_with_clusters(
+        stats: &mut Option<Self>,
+        clusters: &[Cluster],
+        sat: Satellite,
+        sector: Sector,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_scan_angle: f64,
+    ) {
+        // The best clusters from this image so far. Only clone them into the stats at the end, so
+        // a cluster that is beaten later in the same image is never cloned.
+        let mut biggest: Option<&Cluster> = None;
+        let mut hottest: Option<&Cluster> = None;
+
+        for cluster in clusters {
+            if cluster.max_scan_angle() >= max_scan_angle {
+                continue;
             }
 
             if stats.is_none() {
@@ -489,13 +599,17 @@ impl ClusterStats {
 
             if let Some(stats) = stats {
                 let power = cluster.total_power();
-                if stats.biggest_fire.fire.total_power() < power {
-                    ClusterStat::update(&mut stats.biggest_fire, cluster, sat, sector, start, end);
+                let biggest_power = biggest.unwrap_or(&stats.biggest_fire.fire).total_power();
+                if biggest_power < power {
+                    biggest = Some(cluster);
                 }
 
                 let max_temp = cluster.max_temperature();
-                if stats.hottest_fire.fire.max_temperature() < max_temp {
-                    ClusterStat::update(&mut stats.hottest_fire, cluster, sat, sector, start, end);
+                let hottest_temp = hottest
+                    .unwrap_or(&stats.hottest_fire.fire)
+                    .max_temperature();
+                if hottest_temp < max_temp {
+                    hottest = Some(cluster);
                 }
 
                 if power < 1.0 {
@@ -527,6 +641,16 @@ impl ClusterStats {
                 unreachable!()
             }
         }
+
+        if let Some(stats) = stats {
+            if let Some(cluster) = biggest {
+                ClusterStat::update(&mut stats.biggest_fire, cluster, sat, sector, start, end);
+            }
+
+            if let Some(cluster) = hottest {
+                ClusterStat::update(&mut stats.hottest_fire, cluster, sat, sector, start, end);
+            }
+        }
     }
 }
 
@@ -682,6 +806,9 @@ fn create_standard_dir_filter(
      *   G16/ABI-L2-FDCF/2020/238/15/...files...
      */
 
+    // Any directory named for a year before the first satellite was operational can't be a year.
+    let min_year = Satellite::earliest_operational().year();
+
     move |entry| -> bool {
         if entry.path().is_file() {
             // We're only concerned with trimming directories - at this point.
@@ -725,8 +852,9 @@ fn create_standard_dir_filter(
                     if sub_path.len() >= 4 {
                         // Try to parse the year
                         if let Ok(possible_year) = sub_path[..4].parse::<i32>() {
-                            // If it's larger than 2016, it's probably the year.
-                            if possible_year > 2016 {
+                            // If it's not before the first satellite was operational, it's
+                            // probably the year.
+                            if possible_year >= min_year {
                                 year = possible_year;
 
                                 // Return early if we can
@@ -800,29 +928,14 @@ fn create_standard_dir_filter(
     }
 }
 
-fn is_cluster_a_keeper(cluster: &Cluster) -> bool {
+fn is_cluster_a_keeper(cluster: &Cluster, max_scan_angle: f64) -> bool {
     // Check if it meets our mask criteria
-    let keep_mask_criteria = cluster.pixels().pixels().iter().any(|pixel| {
-        match pixel.mask_flag.0 {
-            10   // good_fire_pixel
-            | 11 // saturated_fire_pixel
-            | 12 // cloud_contaminated_fire_pixel
-            | 13 // high_probability_fire_pixel
-            | 14 // medium_probability_fire_pixel
-
-            | 30 // temporally_filtered_good_fire_pixel
-            | 31 // temporally_filtered_saturated_fire_pixel
-            | 32 // temporally_filtered_cloud_contaminated_fire_pixel
-            | 33 // temporally_filtered_high_probability_fire_pixel
-            | 34 // temporally_filtered_medium_probablity_fire_pixel
-                => {
-                 true
-            }
-            _ => false
-        }
-    });
+    let keep_mask_criteria = cluster
+        .pixels()
+        .into_iter()
+        .any(|pixel| pixel.mask_flag.is_fire());
 
-    let scan_angle_criteria = cluster.max_scan_angle() < MAX_SCAN_ANGLE;
+    let scan_angle_criteria = cluster.max_scan_angle() < max_scan_angle;
 
     keep_mask_criteria && scan_angle_criteria
 }
@@ -830,10 +943,7 @@ fn is_cluster_a_keeper(cluster: &Cluster) -> bool {
 /*-------------------------------------------------------------------------------------------------
  *                             Save a Cluster in a KMZ File
  *-----------------------------------------------------------------------------------------------*/
-fn save_cluster_stats_kmz<P: AsRef<Path>>(
-    path: P,
-    cluster_stats: &ClusterStats,
-) -> SatFireResult<()> {
+fn save_cluster_stats_kmz<P: AsRef<Path>>(path: P, cluster_stats: &ClusterStats) -> AppResult<()> {
     let mut kmz = KmzFile::new(path)?;
 
     kmz.start_style(Some("fire"))?;
@@ -854,7 +964,7 @@ fn output_cluster_stat_kml<K: KmlWriter>(
     out: &mut K,
     label: &str,
     cluster: &ClusterStat,
-) -> SatFireResult<()> {
+) -> AppResult<()> {
     let description = format!(
         concat!(
             "Satellite: {}<br/>",
@@ -880,9 +990,257 @@ fn output_cluster_stat_kml<K: KmlWriter>(
     out.create_point(centroid.lat, centroid.lon, 0.0)?;
     out.finish_placemark()?;
 
-    cluster.fire.pixels().kml_write(out);
+    cluster.fire.pixels().kml_write(out, None);
 
     out.finish_folder()?;
 
     Ok(())
 }
+
+/*-------------------------------------------------------------------------------------------------
+ *                             Save the Statistics in a JSON File
+ *-----------------------------------------------------------------------------------------------*/
+fn save_stats_json<P: AsRef<Path>>(
+    path: P,
+    cluster_stats: &ClusterStats,
+    cluster_list_stats: &ClusterListStats,
+) -> AppResult<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_stats_json(&mut out, cluster_stats, cluster_list_stats)?;
+    out.flush()?;
+
+    Ok(())
+}
+
+fn write_stats_json<W: Write>(
+    out: &mut W,
+    cluster_stats: &ClusterStats,
+    cluster_list_stats: &ClusterListStats,
+) -> std::io::Result<()> {
+    let histogram = [
+        (1.0, cluster_stats.num_power_lt_1mw),
+        (10.0, cluster_stats.num_power_lt_10mw),
+        (100.0, cluster_stats.num_power_lt_100mw),
+        (1_000.0, cluster_stats.num_power_lt_1gw),
+        (10_000.0, cluster_stats.num_power_lt_10gw),
+        (100_000.0, cluster_stats.num_power_lt_100gw),
+    ];
+
+    write!(out, "{{\"clusters\":{{")?;
+    write!(out, "\"num_clusters\":{},", cluster_stats.num_clusters)?;
+    write!(out, "\"biggest_fire\":")?;
+    write_cluster_stat_json(out, &cluster_stats.biggest_fire)?;
+    write!(out, ",\"hottest_fire\":")?;
+    write_cluster_stat_json(out, &cluster_stats.hottest_fire)?;
+
+    // The buckets are cumulative, each one counts all the clusters below its power limit.
+    write!(out, ",\"power_histogram\":[")?;
+    for (i, (limit, count)) in histogram.iter().enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        write!(out, "{{\"power_lt_mw\":{},\"count\":{}}}", limit, count)?;
+    }
+    write!(out, "]}},")?;
+
+    write!(out, "\"images\":{{\"max_power\":")?;
+    write_cluster_list_stat_json(out, &cluster_list_stats.max_power)?;
+    write!(out, ",\"min_power\":")?;
+    write_cluster_list_stat_json(out, &cluster_list_stats.min_power)?;
+    write!(out, ",\"max_num_clusters\":")?;
+    write_cluster_list_stat_json(out, &cluster_list_stats.max_num)?;
+    write!(out, ",\"min_num_clusters\":")?;
+    write_cluster_list_stat_json(out, &cluster_list_stats.min_num)?;
+    writeln!(out, "}}}}")
+}
+
+fn write_cluster_stat_json<W: Write>(out: &mut W, cluster: &ClusterStat) -> std::io::Result<()> {
+    let centroid = cluster.fire.centroid();
+
+    write!(
+        out,
+        concat!(
+            "{{\"satellite\":\"{}\",\"sector\":\"{}\",\"start\":\"{}\",\"end\":\"{}\",",
+            "\"centroid\":{{\"lat\":{},\"lon\":{}}},\"max_scan_angle\":{},\"pixel_count\":{},",
+            "\"power_mw\":{},\"area_m2\":{},\"max_temperature_k\":{}}}"
+        ),
+        cluster.sat.name(),
+        cluster.sector.name(),
+        cluster.start.to_rfc3339(),
+        cluster.end.to_rfc3339(),
+        json_number(centroid.lat),
+        json_number(centroid.lon),
+        json_number(cluster.fire.max_scan_angle()),
+        cluster.fire.pixel_count(),
+        json_number(cluster.fire.total_power()),
+        json_number(cluster.fire.total_area()),
+        json_number(cluster.fire.max_temperature()),
+    )
+}
+
+fn write_cluster_list_stat_json<W: Write>(
+    out: &mut W,
+    image: &ClusterListStat,
+) -> std::io::Result<()> {
+    write!(
+        out,
+        concat!(
+            "{{\"satellite\":\"{}\",\"sector\":\"{}\",\"start\":\"{}\",\"end\":\"{}\",",
+            "\"num_clusters\":{},\"total_power_mw\":{}}}"
+        ),
+        image.sat.name(),
+        image.sector.name(),
+        image.start.to_rfc3339(),
+        image.end.to_rfc3339(),
+        image.num_clusters,
+        json_number(image.total_power),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use satfire::PixelList;
+
+    #[test]
+    fn test_cluster_stats_skip_high_scan_angle_clusters() {
+        let cluster = |power: f64, max_scan_angle: f64| {
+            Cluster::new(power, 0.0, 500.0, max_scan_angle, PixelList::new())
+        };
+
+        let clusters = [
+            cluster(10.0, 1.0),
+            cluster(1_000_000.0, DEFAULT_MAX_SCAN_ANGLE + 1.0),
+            cluster(50.0, 2.0),
+        ];
+
+        let time = Utc::now();
+        let mut stats = None;
+        ClusterStats::update_with_clusters(
+            &mut stats,
+            &clusters,
+            Satellite::G17,
+            Sector::FULL,
+            time,
+            time,
+            DEFAULT_MAX_SCAN_ANGLE,
+        );
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.num_clusters, 2);
+        assert_eq!(stats.biggest_fire.fire.total_power(), 50.0);
+
+        // A tighter cutoff from the command line.
+        let mut stats = None;
+        ClusterStats::update_with_clusters(
+            &mut stats,
+            &clusters,
+            Satellite::G17,
+            Sector::FULL,
+            time,
+            time,
+            1.5,
+        );
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.num_clusters, 1);
+        assert_eq!(stats.biggest_fire.fire.total_power(), 10.0);
+    }
+
+    #[test]
+    fn test_cluster_stats_keep_best_across_images() {
+        let cluster = |power: f64, max_temperature: f64| {
+            Cluster::new(power, 0.0, max_temperature, 1.0, PixelList::new())
+        };
+
+        let first = Utc::now();
+        let second = first + chrono::Duration::minutes(10);
+        let mut stats = None;
+        let mut update = |clusters: &[Cluster], time| {
+            ClusterStats::update_with_clusters(
+                &mut stats,
+                clusters,
+                Satellite::G17,
+                Sector::FULL,
+                time,
+                time,
+                DEFAULT_MAX_SCAN_ANGLE,
+            )
+        };
+
+        // Increasing power within an image, the hottest is in the middle.
+        update(
+            &[
+                cluster(10.0, 500.0),
+                cluster(20.0, 900.0),
+                cluster(30.0, 600.0),
+            ],
+            first,
+        );
+        // Hotter but not bigger, and ties don't replace the earlier cluster.
+        update(&[cluster(30.0, 1_000.0), cluster(5.0, 400.0)], second);
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.num_clusters, 5);
+        assert_eq!(stats.biggest_fire.fire.total_power(), 30.0);
+        assert_eq!(stats.biggest_fire.fire.max_temperature(), 600.0);
+        assert_eq!(stats.biggest_fire.start, first);
+        assert_eq!(stats.hottest_fire.fire.max_temperature(), 1_000.0);
+        assert_eq!(stats.hottest_fire.start, second);
+    }
+
+    #[test]
+    fn test_stats_json() {
+        let cluster = |power: f64| Cluster::new(power, 0.0, 500.0, 1.0, PixelList::new());
+        let clusters = [cluster(0.5), cluster(50.0), cluster(5_000.0)];
+
+        let time = Utc::now();
+        let mut stats = None;
+        ClusterStats::update_with_clusters(
+            &mut stats,
+            &clusters,
+            Satellite::G17,
+            Sector::FULL,
+            time,
+            time,
+            DEFAULT_MAX_SCAN_ANGLE,
+        );
+        let stats = stats.unwrap();
+
+        let image = ClusterListStat {
+            sat: Satellite::G17,
+            sector: Sector::FULL,
+            num_clusters: 3,
+            total_power: 5_050.5,
+            start: time,
+            end: time,
+        };
+        let list_stats = ClusterListStats {
+            min_num: image,
+            max_num: image,
+            max_power: image,
+            min_power: image,
+        };
+
+        let mut buf = vec![];
+        write_stats_json(&mut buf, &stats, &list_stats).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+
+        let clusters = &json["clusters"];
+        assert_eq!(clusters["num_clusters"], 3);
+        assert_eq!(clusters["biggest_fire"]["power_mw"], 5_000.0);
+        assert_eq!(clusters["biggest_fire"]["satellite"], "G17");
+        assert_eq!(clusters["biggest_fire"]["sector"], "FDCF");
+        // There are no pixels, so the centroid isn't defined.
+        assert!(clusters["biggest_fire"]["centroid"]["lat"].is_null());
+
+        let histogram = clusters["power_histogram"].as_array().unwrap();
+        assert_eq!(histogram.len(), 6);
+        assert_eq!(histogram[0]["power_lt_mw"], 1.0);
+        assert_eq!(histogram[0]["count"], 1);
+        assert_eq!(histogram[2]["count"], 2);
+        assert_eq!(histogram[5]["count"], 3);
+
+        assert_eq!(json["images"]["max_power"]["total_power_mw"], 5_050.5);
+    }
+}
@@ -1,14 +1,16 @@
 use chrono::{DateTime, NaiveDateTime, Utc};
 use clap::Parser;
-use log::info;
+use log::{info, warn, LevelFilter};
 use satfire::{
-    BoundingBox, ClusterDatabase, ClusterDatabaseClusterRow, Coord, KmlWriter, KmzFile,
-    SatFireResult, Satellite, Sector,
+    AppResult, BoundingBox, ClusterDatabase, ClusterDatabaseClusterRow, Coord, KmlWriter, KmzFile,
+    Satellite, Sector,
 };
 use simple_logger::SimpleLogger;
 use std::{
     fmt::{self, Display},
+    io::{BufWriter, Write},
     path::PathBuf,
+    str::FromStr,
 };
 use strum::IntoEnumIterator;
 
@@ -18,7 +20,8 @@ use strum::IntoEnumIterator;
 ///
 /// Export clusters into a KMZ file.
 ///
-/// This program will export all the clusters in a requested region and time range into a KMZ file.
+/// This program will export all the clusters in a requested region and time range into a KMZ file,
+/// or as newline delimited JSON.
 ///
 #[derive(Debug, Parser)]
 #[clap(bin_name = "showclusters")]
@@ -39,11 +42,19 @@ struct ShowClustersOptionsInit {
     #[clap(short, long)]
     kmz_file: Option<PathBuf>,
 
-    /// The start time (UTC) for the export in the format YYYY-MM-DD-HH
+    /// The output format, kmz or ndjson.
+    ///
+    /// With ndjson, the clusters are written to standard output as newline delimited JSON, one
+    /// object per cluster, instead of to the KMZ file. Logging is turned off with this format so
+    /// only JSON is written to standard output.
+    #[clap(long, default_value = "kmz")]
+    format: OutputFormat,
+
+    /// The start time (UTC) for the export in the format YYYY-MM-DD-HH[:MM[:SS]]
     #[clap(parse(try_from_str=parse_datetime))]
     start: DateTime<Utc>,
 
-    /// The end time (UTC) for the export in the format YYYY-MM-DD-HH
+    /// The end time (UTC) for the export in the format YYYY-MM-DD-HH[:MM[:SS]]
     #[clap(parse(try_from_str=parse_datetime))]
     end: DateTime<Utc>,
 
@@ -52,13 +63,40 @@ struct ShowClustersOptionsInit {
     #[clap(default_value_t=BoundingBox{ll:Coord{lat: 44.0, lon: -116.5}, ur:Coord{lat: 49.5, lon: -104.0}})]
     bbox: BoundingBox,
 
+    /// Expand the bounding box by this many degrees on every side.
+    ///
+    /// Clusters are selected by their centroid, so a margin catches clusters with pixels inside
+    /// the bounding box but a centroid just outside of it.
+    #[clap(short, long, default_value_t = 0.0)]
+    #[clap(parse(try_from_str=parse_margin))]
+    margin: f64,
+
     /// Verbose output
     #[clap(short, long)]
     verbose: bool,
 }
 
+/// The formats showclusters can write the clusters in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Kmz,
+    NdJson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kmz" => Ok(OutputFormat::Kmz),
+            "ndjson" => Ok(OutputFormat::NdJson),
+            _ => Err(format!("unknown format {}, expected kmz or ndjson", s)),
+        }
+    }
+}
+
 /// Parse a bounding box argument.
-fn parse_bbox(bbox_str: &str) -> SatFireResult<BoundingBox> {
+fn parse_bbox(bbox_str: &str) -> AppResult<BoundingBox> {
     let corners: Vec<_> = bbox_str.split(',').collect();
 
     if corners.len() < 4 {
@@ -105,14 +143,35 @@ fn parse_bbox(bbox_str: &str) -> SatFireResult<BoundingBox> {
 }
 
 /// Parse a command line datetime
-fn parse_datetime(dt_str: &str) -> SatFireResult<DateTime<Utc>> {
+fn parse_datetime(dt_str: &str) -> AppResult<DateTime<Utc>> {
     const TIME_FORMAT: &str = "%Y-%m-%d-%H:%M:%S";
-    let t_str = format!("{}:00:00", dt_str);
+
+    // Default any missing minutes and seconds to zero.
+    let t_str = match dt_str.matches(':').count() {
+        0 => format!("{}:00:00", dt_str),
+        1 => format!("{}:00", dt_str),
+        _ => dt_str.to_owned(),
+    };
 
     let naive = NaiveDateTime::parse_from_str(&t_str, TIME_FORMAT)?;
     Ok(DateTime::from_utc(naive, Utc))
 }
 
+/// Parse a bounding box margin argument.
+fn parse_margin(margin_str: &str) -> AppResult<f64> {
+    let margin: f64 = margin_str.parse()?;
+
+    if !margin.is_finite() || margin < 0.0 {
+        return Err(format!(
+            "Margin must be a finite number that isn't negative: {}",
+            margin
+        )
+        .into());
+    }
+
+    Ok(margin)
+}
+
 #[derive(Debug)]
 struct ShowClustersOptionsChecked {
     /// The path to the database file.
@@ -121,6 +180,9 @@ struct ShowClustersOptionsChecked {
     /// The path to a KMZ file to produce from this run.
     kmz_file: PathBuf,
 
+    /// The output format.
+    format: OutputFormat,
+
     /// The start time.
     start: DateTime<Utc>,
 
@@ -138,7 +200,10 @@ impl Display for ShowClustersOptionsChecked {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         writeln!(f, "\n")?; // yes, two blank lines.
         writeln!(f, "    Database: {}", self.cluster_store_file.display())?;
-        writeln!(f, "  Output KMZ: {}", self.kmz_file.display())?;
+        match self.format {
+            OutputFormat::Kmz => writeln!(f, "  Output KMZ: {}", self.kmz_file.display())?,
+            OutputFormat::NdJson => writeln!(f, "      Output: standard output (NDJSON)")?,
+        }
         writeln!(f, "       Start: {}", self.start)?;
         writeln!(f, "         End: {}", self.end)?;
         writeln!(
@@ -146,6 +211,7 @@ impl Display for ShowClustersOptionsChecked {
             "Bounding Box: ({:.6}, {:.6}) <---> ({:.6}, {:.6})",
             self.bbox.ll.lat, self.bbox.ll.lon, self.bbox.ur.lat, self.bbox.ur.lon
         )?;
+        writeln!(f, "        Area: {:.0} km^2", self.bbox.area_km2())?;
         writeln!(f, "\n")?; // yes, two blank lines.
 
         Ok(())
@@ -155,16 +221,20 @@ impl Display for ShowClustersOptionsChecked {
 /// Get the command line arguments and check them.
 ///
 /// If there is missing data, try to fill it in with environment variables.
-fn parse_args() -> SatFireResult<ShowClustersOptionsChecked> {
+fn parse_args() -> AppResult<ShowClustersOptionsChecked> {
     let ShowClustersOptionsInit {
         cluster_store_file,
         kmz_file,
+        format,
         start,
         end,
         bbox,
+        margin,
         verbose,
     } = ShowClustersOptionsInit::parse();
 
+    let bbox = bbox.expanded_by(margin, margin);
+
     let kmz_file = match kmz_file {
         Some(v) => v,
         None => {
@@ -177,29 +247,46 @@ fn parse_args() -> SatFireResult<ShowClustersOptionsChecked> {
     let checked = ShowClustersOptionsChecked {
         cluster_store_file,
         kmz_file,
+        format,
         start,
         end,
         bbox,
         verbose,
     };
 
-    if verbose {
-        info!("{}", checked);
-    }
-
     Ok(checked)
 }
 
 /*-------------------------------------------------------------------------------------------------
  *                                             MAIN
  *-----------------------------------------------------------------------------------------------*/
-fn main() -> SatFireResult<()> {
-    SimpleLogger::new().init()?;
-
+fn main() -> AppResult<()> {
     let opts = parse_args()?;
 
+    // The logger writes to standard output, so keep it quiet when that is where the clusters go.
+    let mut logger = SimpleLogger::new();
+    if opts.format == OutputFormat::NdJson {
+        logger = logger.with_level(LevelFilter::Off);
+    }
+    logger.init()?;
+
+    if opts.verbose {
+        info!("{}", opts);
+    }
+
     let db = ClusterDatabase::connect(&opts.cluster_store_file)?;
+
+    if opts.format == OutputFormat::NdJson {
+        let stdout = std::io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        db.write_ndjson(None, None, opts.start, opts.end, opts.bbox, &mut out)?;
+        out.flush()?;
+
+        return Ok(());
+    }
+
     let mut kfile = KmzFile::new(&opts.kmz_file)?;
+    let mut num_clusters: usize = 0;
 
     for sat in Satellite::iter() {
         kfile.start_folder(Some(sat.name()), None, false)?;
@@ -207,8 +294,14 @@ fn main() -> SatFireResult<()> {
         for sector in Sector::iter() {
             kfile.start_folder(Some(sector.name()), None, false)?;
 
-            let mut query =
-                db.query_clusters(Some(sat), Some(sector), opts.start, opts.end, opts.bbox)?;
+            let mut query = db.query_clusters(
+                Some(sat),
+                Some(sector),
+                opts.start,
+                opts.end,
+                opts.bbox,
+                None,
+            )?;
 
             for row_res in query.rows()? {
                 let ClusterDatabaseClusterRow {
@@ -223,10 +316,11 @@ fn main() -> SatFireResult<()> {
                     }
                 };
 
+                num_clusters += 1;
                 kfile.start_folder(Some("Folder"), None, false)?;
 
                 kfile.timespan(start, end)?;
-                pixels.kml_write(&mut kfile);
+                pixels.kml_write(&mut kfile, None);
 
                 kfile.finish_folder()?;
             }
@@ -237,5 +331,62 @@ fn main() -> SatFireResult<()> {
         kfile.finish_folder()?;
     }
 
+    if num_clusters == 0 {
+        warn!("No clusters matched the query.");
+
+        let coverage = db.coverage_summary()?;
+        if coverage.is_empty() {
+            info!("The database has no clusters.");
+        } else {
+            info!("Available data:");
+            for entry in coverage {
+                info!("    {}", entry);
+            }
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_datetime() {
+        assert_eq!(
+            parse_datetime("2022-07-04-18").unwrap(),
+            Utc.ymd(2022, 7, 4).and_hms(18, 0, 0)
+        );
+        assert_eq!(
+            parse_datetime("2022-07-04-18:21").unwrap(),
+            Utc.ymd(2022, 7, 4).and_hms(18, 21, 0)
+        );
+        assert_eq!(
+            parse_datetime("2022-07-04-18:21:37").unwrap(),
+            Utc.ymd(2022, 7, 4).and_hms(18, 21, 37)
+        );
+
+        assert!(parse_datetime("2022-07-04").is_err());
+        assert!(parse_datetime("2022-07-04-18:61").is_err());
+    }
+
+    #[test]
+    fn test_parse_margin() {
+        assert_eq!(parse_margin("0").unwrap(), 0.0);
+        assert_eq!(parse_margin("0.5").unwrap(), 0.5);
+
+        assert!(parse_margin("-0.5").is_err());
+        assert!(parse_margin("NaN").is_err());
+        assert!(parse_margin("inf").is_err());
+        assert!(parse_margin("wide").is_err());
+    }
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!("kmz".parse::<OutputFormat>(), Ok(OutputFormat::Kmz));
+        assert_eq!("ndjson".parse::<OutputFormat>(), Ok(OutputFormat::NdJson));
+        assert!("json".parse::<OutputFormat>().is_err());
+    }
+}
@@ -1,9 +1,7 @@
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use clap::Parser;
 use log::info;
-use satfire::{
-    BoundingBox, Coord, FiresDatabase, Geo, KmlWriter, KmzFile, SatFireResult, Satellite,
-};
+use satfire::{AppResult, BoundingBox, Coord, FiresDatabase, Geo, KmlWriter, KmzFile, Satellite};
 use simple_logger::SimpleLogger;
 use std::{
     fmt::{self, Display},
@@ -62,7 +60,7 @@ struct ShowFiresOptionsInit {
 }
 
 /// Parse a bounding box argument.
-fn parse_bbox(bbox_str: &str) -> SatFireResult<BoundingBox> {
+fn parse_bbox(bbox_str: &str) -> AppResult<BoundingBox> {
     let corners: Vec<_> = bbox_str.split(',').collect();
 
     if corners.len() < 4 {
@@ -109,7 +107,7 @@ fn parse_bbox(bbox_str: &str) -> SatFireResult<BoundingBox> {
 }
 
 /// Parse a command line datetime
-fn parse_datetime(dt_str: &str) -> SatFireResult<DateTime<Utc>> {
+fn parse_datetime(dt_str: &str) -> AppResult<DateTime<Utc>> {
     const TIME_FORMAT: &str = "%Y-%m-%d-%H:%M:%S";
     let t_str = format!("{}:00:00", dt_str);
 
@@ -163,7 +161,7 @@ impl Display for ShowFiresOptionsChecked {
 /// Get the command line arguments and check them.
 ///
 /// If there is missing data, try to fill it in with environment variables.
-fn parse_args() -> SatFireResult<ShowFiresOptionsChecked> {
+fn parse_args() -> AppResult<ShowFiresOptionsChecked> {
     let ShowFiresOptionsInit {
         fires_store_file,
         kmz_file,
@@ -203,7 +201,7 @@ fn parse_args() -> SatFireResult<ShowFiresOptionsChecked> {
 /*-------------------------------------------------------------------------------------------------
  *                                             MAIN
  *-----------------------------------------------------------------------------------------------*/
-fn main() -> SatFireResult<()> {
+fn main() -> AppResult<()> {
     SimpleLogger::new().init()?;
 
     let opts = parse_args()?;
@@ -271,7 +269,7 @@ fn main() -> SatFireResult<()> {
                     kfile.create_point(lat, lon, 0.0)?;
                     kfile.finish_placemark()?;
 
-                    pixels.kml_write(&mut kfile);
+                    pixels.kml_write(&mut kfile, None);
 
                     kfile.finish_folder()?;
                 }
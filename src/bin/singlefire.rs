@@ -1,6 +1,6 @@
 use clap::Parser;
 use log::info;
-use satfire::{Coord, Geo, JointFiresClusterDatabases, KmlWriter, KmzFile, SatFireResult};
+use satfire::{AppResult, Coord, Geo, JointFiresClusterDatabases, KmlWriter, KmzFile};
 use simple_logger::SimpleLogger;
 use std::{
     fmt::{self, Display, Write},
@@ -62,7 +62,7 @@ impl Display for SingleFireOptions {
 /// Get the command line arguments and check them.
 ///
 /// If there is missing data, try to fill it in with environment variables.
-fn parse_args() -> SatFireResult<SingleFireOptions> {
+fn parse_args() -> AppResult<SingleFireOptions> {
     let opts = SingleFireOptions::parse();
 
     if opts.verbose {
@@ -75,7 +75,7 @@ fn parse_args() -> SatFireResult<SingleFireOptions> {
 /*-------------------------------------------------------------------------------------------------
  *                                             Main
  *-----------------------------------------------------------------------------------------------*/
-fn main() -> SatFireResult<()> {
+fn main() -> AppResult<()> {
     SimpleLogger::new().init()?;
 
     let opts = parse_args()?;
@@ -151,7 +151,7 @@ fn main() -> SatFireResult<()> {
             kfile.start_placemark(None, Some(&description), Some("#fire"))?;
             kfile.create_point(lat, lon, 0.0)?;
             kfile.finish_placemark()?;
-            pixels.kml_write(&mut kfile);
+            pixels.kml_write(&mut kfile, None);
             kfile.finish_folder()?;
         }
     }
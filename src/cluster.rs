@@ -1,13 +1,17 @@
 use crate::{
-    end_time_from_file_name,
-    firesatimage::{FirePoint, SatFireImage},
+    firesatimage::{FirePoint, ImageBuffers, SatFireImage},
     geo::{BoundingBox, Coord, Geo},
-    pixel::PixelList,
+    pixel::{Pixel, PixelList},
     satellite::{Satellite, Sector},
-    start_time_from_file_name, SatFireResult,
+    SatFireError,
 };
 use chrono::{DateTime, Utc};
-use std::path::Path;
+use rustc_hash::FxHashMap as HashMap;
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    path::Path,
+};
 
 /** Represents a spatially contiguous cluster of [Pixel](crate::Pixel) objects.
  *
@@ -72,6 +76,21 @@ impl Cluster {
         self.area
     }
 
+    /// Get the area of the ground covered by the pixels in the Cluster, square meters.
+    ///
+    /// Unlike [Cluster::total_area], which is the fire area reported by the satellite, this is
+    /// the sum of [Pixel::geographic_area_m2] for every pixel. That treats the Earth as a sphere
+    /// with its mean radius, which is accurate to within about half a percent. Pixels from the
+    /// same scan don't overlap, so this is the area of the cluster's footprint. Pixels get larger
+    /// toward the limb of the Earth, so this is a better measure of size than the pixel count.
+    pub fn geographic_area_m2(&self) -> f64 {
+        self.pixels
+            .pixels()
+            .iter()
+            .map(|pixel| pixel.geographic_area_m2())
+            .sum()
+    }
+
     /// Get the max fire temperature of all pixels in the Cluster that had a temperature in the
     /// file, Kelvin.
     pub fn max_temperature(&self) -> f64 {
@@ -93,9 +112,34 @@ impl Cluster {
         &self.pixels
     }
 
+    /// Merge another Cluster into this one.
+    ///
+    /// The pixels are combined with [PixelList::max_merge], so a pixel that is in both clusters,
+    /// such as one scanned in overlapping sectors at the same time, is only counted once. The
+    /// total power, total area, max temperature, and max scan angle are recomputed from the merged
+    /// pixels.
+    ///
+    /// Clusters don't know which satellite, sector, or scan they came from, so it is up to the
+    /// caller to make sure merging them makes sense. Usually the clusters should come from
+    /// [ClusterList]s with the same satellite and scan times.
+    pub fn merge(&mut self, other: &Cluster) {
+        let mut pixels = std::mem::take(&mut self.pixels);
+        pixels.max_merge(&other.pixels);
+
+        *self = Cluster::default();
+        for pixel in pixels {
+            self.add_pixel(pixel);
+        }
+    }
+
     /// Add a fire point to this Cluster.
     fn add_fire_point(&mut self, fire_point: FirePoint) {
         let FirePoint { pixel, .. } = fire_point;
+        self.add_pixel(pixel);
+    }
+
+    /// Add a pixel to this Cluster and update the aggregate properties.
+    fn add_pixel(&mut self, pixel: Pixel) {
         self.pixels.push(pixel);
 
         if pixel.power.is_finite() {
@@ -124,6 +168,28 @@ impl Geo for Cluster {
     }
 }
 
+/// How pixels in the image grid are connected when grouping them into clusters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Connectivity {
+    /// Pixels are connected only if they share an edge in the grid.
+    Four,
+    /// Pixels are connected if they share an edge or a corner in the grid, so diagonally touching
+    /// pixels are in the same cluster.
+    #[default]
+    Eight,
+}
+
+impl Connectivity {
+    /// The offsets to half of the neighbors of a grid cell, the ones in the row above and to the
+    /// left. Checking these for every cell covers every pair of neighbors exactly once.
+    fn half_neighborhood(self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &[(0, -1), (-1, 0)],
+            Connectivity::Eight => &[(-1, -1), (0, -1), (1, -1), (-1, 0)],
+        }
+    }
+}
+
 /** A collection of [Cluster](crate::Cluster) objects.
  *
  * This collection stores a list of Clusters that are related. Specifically, they all come from
@@ -142,6 +208,23 @@ pub struct ClusterList {
 }
 
 impl ClusterList {
+    /// Create a new ClusterList from its parts.
+    pub(crate) fn new(
+        satellite: Satellite,
+        sector: Sector,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        clusters: Vec<Cluster>,
+    ) -> Self {
+        ClusterList {
+            satellite,
+            sector,
+            start,
+            end,
+            clusters,
+        }
+    }
+
     /// Get the name of the satellite.
     pub fn satellite(&self) -> Satellite {
         self.satellite
@@ -193,6 +276,17 @@ impl ClusterList {
         self.clusters.retain(filter_func)
     }
 
+    /// Find the cluster with the centroid closest to `coord`.
+    ///
+    /// Returns the cluster and the great circle distance from its centroid to `coord` in meters,
+    /// or `None` if the list is empty.
+    pub fn nearest_cluster(&self, coord: Coord) -> Option<(&Cluster, f64)> {
+        self.clusters
+            .iter()
+            .map(|cluster| (cluster, cluster.centroid().distance_haversine_m(coord)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
     /// Get the number of items in the ClusterList.
     pub fn len(&self) -> usize {
         self.clusters.len()
@@ -211,27 +305,49 @@ impl ClusterList {
     }
     /// Analyze a file and return a ClusterList.
     ///
-    /// The metadata is gleaned from the file name, so this program relies on the current naming
-    /// conventions of the NOAA big data program.
-    pub fn from_file<P: AsRef<Path>>(full_path: P) -> SatFireResult<ClusterList> {
+    /// The satellite and sector are gleaned from the file name, so this program relies on the
+    /// current naming conventions of the NOAA big data program. The scan times come from the file
+    /// metadata when available, and fall back to the file name otherwise. See
+    /// [ClusterListOptions] for how the clustering is done.
+    ///
+    /// An image without any fire points is not an error, it results in an empty list. See
+    /// [ClusterListError] for the kinds of errors.
+    pub fn from_file<P: AsRef<Path>>(
+        full_path: P,
+        options: ClusterListOptions,
+    ) -> Result<ClusterList, ClusterListError> {
+        use ClusterListError::{ReadFailure, UnrecognizedFile};
+
         let path: &Path = full_path.as_ref();
         let fname = path
             .file_name()
-            .ok_or_else(|| "No file name".to_string())?
+            .ok_or(UnrecognizedFile("no file name"))?
             .to_string_lossy();
 
-        let satellite = Satellite::string_contains_satellite(&fname)
-            .ok_or_else(|| "No satellite".to_string())?;
-        let sector =
-            Sector::string_contains_sector(&fname).ok_or_else(|| "No sector".to_string())?;
+        let satellite =
+            Satellite::string_contains_satellite(&fname).ok_or(UnrecognizedFile("no satellite"))?;
+        let sector = Sector::string_contains_sector(&fname).ok_or(UnrecognizedFile("no sector"))?;
+
+        let fdata = SatFireImage::open(path).map_err(ReadFailure)?;
 
-        let start =
-            start_time_from_file_name(&fname).ok_or_else(|| "No start time.".to_string())?;
-        let end = end_time_from_file_name(&fname).ok_or_else(|| "No end time".to_string())?;
+        let start = fdata
+            .start_time()
+            .ok_or(UnrecognizedFile("no start time"))?;
+        let end = fdata.end_time().ok_or(UnrecognizedFile("no end time"))?;
 
-        let fdata = SatFireImage::open(path)?;
-        let points = fdata.extract_fire_points()?;
-        let clusters: Vec<Cluster> = clusters_from_fire_points(points);
+        let ClusterListOptions {
+            threads,
+            connectivity,
+            buffers,
+        } = options;
+
+        let points = match buffers {
+            Some(buffers) => fdata.extract_fire_points_with_buffers(buffers),
+            None => fdata.extract_fire_points_with_buffers(&mut ImageBuffers::new()),
+        }
+        .map_err(ReadFailure)?;
+        let clusters: Vec<Cluster> =
+            clusters_from_fire_points_parallel(points, threads, connectivity);
 
         Ok(ClusterList {
             satellite,
@@ -243,53 +359,646 @@ impl ClusterList {
     }
 }
 
-fn clusters_from_fire_points(mut points: Vec<FirePoint>) -> Vec<Cluster> {
-    let mut clusters: Vec<Cluster> = vec![];
-    let mut cluster_points: Vec<FirePoint> = Vec::with_capacity(20);
+/// Options for loading a [ClusterList] with [ClusterList::from_file].
+///
+/// The defaults are a single thread, [Connectivity::Eight], and new buffers for every file.
+#[derive(Debug)]
+pub struct ClusterListOptions<'a> {
+    threads: usize,
+    connectivity: Connectivity,
+    buffers: Option<&'a mut ImageBuffers>,
+}
 
-    for i in 0..points.len() {
-        let fp = unsafe { points.get_unchecked_mut(i) };
+impl<'a> Default for ClusterListOptions<'a> {
+    fn default() -> Self {
+        ClusterListOptions {
+            threads: 1,
+            connectivity: Connectivity::default(),
+            buffers: None,
+        }
+    }
+}
+
+impl<'a> ClusterListOptions<'a> {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use multiple threads for the clustering.
+    ///
+    /// The image is split into horizontal bands that are clustered in parallel, and then clusters
+    /// that touch across the band boundaries are stitched back together. The result is the same
+    /// as with a single thread. This is only worthwhile for large images with many fire points,
+    /// like the full disk during a busy fire season.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Set how pixels in the image grid are connected into clusters.
+    pub fn connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    /// Read the data from the file into `buffers`.
+    ///
+    /// Reusing the same buffers for a series of files avoids allocating space for several copies
+    /// of the whole image for every file.
+    pub fn buffers(mut self, buffers: &'a mut ImageBuffers) -> Self {
+        self.buffers = Some(buffers);
+        self
+    }
+}
+
+/// The reasons loading a [ClusterList] from a file can fail.
+#[derive(Debug)]
+pub enum ClusterListError {
+    /// The file isn't recognized as a GOES fire detection file, e.g. the satellite, sector, or
+    /// scan times couldn't be determined. The message says which part was missing.
+    UnrecognizedFile(&'static str),
+    /// There was an error opening or reading the data in the file.
+    ReadFailure(SatFireError),
+}
+
+impl Display for ClusterListError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            ClusterListError::UnrecognizedFile(msg) => write!(f, "unrecognized file: {}", msg),
+            ClusterListError::ReadFailure(err) => write!(f, "read failure: {}", err),
+        }
+    }
+}
+
+impl Error for ClusterListError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClusterListError::UnrecognizedFile(_) => None,
+            ClusterListError::ReadFailure(err) => Some(err),
+        }
+    }
+}
+
+/// Group the fire points into clusters of points that are connected through a chain of neighbors.
+///
+/// Two points are neighbors if they are adjacent in the image grid as defined by connectivity.
+/// This is a union-find keyed on the grid indexes of the points, so it scales linearly with the
+/// number of points. The clusters are in the order of their first point, and the points within a
+/// cluster keep their original order.
+fn clusters_from_fire_points(points: Vec<FirePoint>, connectivity: Connectivity) -> Vec<Cluster> {
+    let all_indexes: Vec<usize> = (0..points.len()).collect();
+    let parents = link_neighbors(&points, &all_indexes, connectivity);
+
+    assemble_clusters(points, parents)
+}
+
+/// Same as clusters_from_fire_points, but the points are split into num_tiles horizontal bands
+/// that are linked in parallel before being stitched together.
+fn clusters_from_fire_points_parallel(
+    points: Vec<FirePoint>,
+    num_tiles: usize,
+    connectivity: Connectivity,
+) -> Vec<Cluster> {
+    if num_tiles <= 1 || points.is_empty() {
+        return clusters_from_fire_points(points, connectivity);
+    }
 
-        if fp.x == isize::MIN && fp.y == isize::MIN {
+    let min_y = points.iter().map(|fp| fp.y).min().unwrap();
+    let (band_height, bands) = split_into_bands(&points, min_y, num_tiles);
+
+    let mut parents: Vec<usize> = vec![0; points.len()];
+    std::thread::scope(|scope| {
+        let points = &points;
+        let handles: Vec<_> = bands
+            .iter()
+            .map(|band| scope.spawn(move || link_neighbors(points, band, connectivity)))
+            .collect();
+
+        for (band, handle) in bands.iter().zip(handles) {
+            let roots = handle.join().expect("Error joining clustering thread");
+            for (&i, root) in band.iter().zip(roots) {
+                parents[i] = root;
+            }
+        }
+    });
+
+    // Stitch together clusters that connect the first row of a band to the last row of the band
+    // above it.
+    let band_row = |fp: &FirePoint| (fp.y - min_y) % band_height;
+    let last_rows: HashMap<(isize, isize), usize> = points
+        .iter()
+        .enumerate()
+        .filter(|(_, fp)| band_row(fp) == band_height - 1)
+        .map(|(i, fp)| ((fp.x, fp.y), i))
+        .collect();
+
+    for (i, fp) in points.iter().enumerate() {
+        if fp.y == min_y || band_row(fp) != 0 {
             continue;
         }
 
-        cluster_points.push(*fp);
+        let neighbors_above = connectivity
+            .half_neighborhood()
+            .iter()
+            .filter(|(_, dy)| *dy == -1);
+        for (dx, dy) in neighbors_above {
+            if let Some(&j) = last_rows.get(&(fp.x + dx, fp.y + dy)) {
+                union(&mut parents, i, j);
+            }
+        }
+    }
+
+    assemble_clusters(points, parents)
+}
 
-        fp.x = isize::MIN;
-        fp.y = isize::MIN;
+/// Split the points into at most num_tiles horizontal bands of equal height, starting at row
+/// min_y.
+///
+/// There are never more bands than rows with points in them, and bands without any points are
+/// left out, so there is no thread started for an empty band. Returns the band height and the
+/// indexes of the points in each band, in ascending order.
+fn split_into_bands(
+    points: &[FirePoint],
+    min_y: isize,
+    num_tiles: usize,
+) -> (isize, Vec<Vec<usize>>) {
+    let mut rows: Vec<isize> = points.iter().map(|fp| fp.y).collect();
+    rows.sort_unstable();
+    rows.dedup();
+    let num_tiles = num_tiles.min(rows.len()).max(1) as isize;
+
+    let num_rows = rows.last().map_or(1, |max_y| max_y - min_y + 1);
+    let band_height = (num_rows + num_tiles - 1) / num_tiles;
+
+    let mut bands: Vec<Vec<usize>> = vec![vec![]; num_tiles as usize];
+    for (i, fp) in points.iter().enumerate() {
+        bands[((fp.y - min_y) / band_height) as usize].push(i);
+    }
+    bands.retain(|band| !band.is_empty());
 
-        for j in (i + 1)..points.len() {
-            let candidate = unsafe { points.get_unchecked_mut(j) };
+    (band_height, bands)
+}
 
-            if candidate.x == isize::MIN && candidate.y == isize::MIN {
-                continue;
+/// Link the points selected by indexes with their neighbors among the selected points.
+///
+/// Returns the index in points of the root of each selected point's group, in the same order as
+/// indexes. Indexes must be in ascending order.
+fn link_neighbors(
+    points: &[FirePoint],
+    indexes: &[usize],
+    connectivity: Connectivity,
+) -> Vec<usize> {
+    let index_of: HashMap<(isize, isize), usize> = indexes
+        .iter()
+        .enumerate()
+        .map(|(k, &i)| ((points[i].x, points[i].y), k))
+        .collect();
+
+    let mut parents: Vec<usize> = (0..indexes.len()).collect();
+
+    for (k, &i) in indexes.iter().enumerate() {
+        let fp = &points[i];
+        for (dx, dy) in connectivity.half_neighborhood() {
+            if let Some(&j) = index_of.get(&(fp.x + dx, fp.y + dy)) {
+                union(&mut parents, k, j);
             }
+        }
+    }
+
+    (0..indexes.len())
+        .map(|k| indexes[find_root(&mut parents, k)])
+        .collect()
+}
+
+/// Collect the points into clusters by the root of their group in parents.
+fn assemble_clusters(points: Vec<FirePoint>, mut parents: Vec<usize>) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = vec![];
+    let mut cluster_index_of_root: HashMap<usize, usize> = HashMap::default();
+
+    for (i, fp) in points.into_iter().enumerate() {
+        let root = find_root(&mut parents, i);
+        let cluster_index = *cluster_index_of_root.entry(root).or_insert_with(|| {
+            clusters.push(Cluster::default());
+            clusters.len() - 1
+        });
+
+        clusters[cluster_index].add_fire_point(fp);
+    }
+
+    clusters
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        // Path halving keeps the trees shallow.
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+
+    i
+}
+
+fn union(parents: &mut [usize], i: usize, j: usize) {
+    let root_i = find_root(parents, i);
+    let root_j = find_root(parents, j);
+
+    // Always keep the smallest index as the root so the cluster order is deterministic.
+    if root_i < root_j {
+        parents[root_j] = root_i;
+    } else {
+        parents[root_i] = root_j;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::satellite::{DataQualityFlagCode, MaskCode};
+
+    fn fire_point(x: isize, y: isize) -> FirePoint {
+        let (lon, lat) = (x as f64, -(y as f64));
+
+        FirePoint {
+            x,
+            y,
+            pixel: Pixel {
+                ul: Coord { lat, lon },
+                ll: Coord {
+                    lat: lat - 1.0,
+                    lon,
+                },
+                lr: Coord {
+                    lat: lat - 1.0,
+                    lon: lon + 1.0,
+                },
+                ur: Coord {
+                    lat,
+                    lon: lon + 1.0,
+                },
+                power: 1.0,
+                area: 1.0,
+                temperature: 500.0,
+                scan_angle: 1.0,
+                mask_flag: MaskCode(10),
+                data_quality_flag: DataQualityFlagCode(0),
+            },
+        }
+    }
+
+    #[test]
+    fn test_cluster_list_from_unrecognized_file() {
+        let err = ClusterList::from_file("not_a_goes_file.nc", ClusterListOptions::new())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            ClusterListError::UnrecognizedFile("no satellite")
+        ));
+
+        let err = ClusterList::from_file(
+            "OR_ABI-L2-FDCX-M6_G17_s20212130100319.nc",
+            ClusterListOptions::new(),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(
+            err,
+            ClusterListError::UnrecognizedFile("no sector")
+        ));
+    }
+
+    #[test]
+    fn test_cluster_list_from_file_options() {
+        // A small image with three connected fire pixels, see the firesatimage tests.
+        let path = format!(
+            "{}/test_data/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            "OR_ABI-L2-FDCC-M6_G17_s20221851801177_e20221851803550_c20221851804313.nc"
+        );
+
+        let clist = ClusterList::from_file(&path, ClusterListOptions::new()).unwrap();
+        assert_eq!(clist.satellite(), Satellite::G17);
+        assert_eq!(clist.sector(), Sector::CONUS);
+        assert_eq!(clist.len(), 1);
+        assert_eq!(clist.clusters()[0].pixel_count(), 3);
+        assert_eq!(clist.total_power(), 240.0);
+
+        let mut buffers = ImageBuffers::new();
+        for connectivity in [Connectivity::Four, Connectivity::Eight] {
+            let options = ClusterListOptions::new()
+                .threads(2)
+                .connectivity(connectivity)
+                .buffers(&mut buffers);
+            let other = ClusterList::from_file(&path, options).unwrap();
+            assert_eq!(other.len(), clist.len());
+            assert_eq!(other.total_power(), clist.total_power());
+        }
+    }
+
+    #[test]
+    fn test_cluster_geographic_area() {
+        let mut cluster = Cluster::default();
+        cluster.add_fire_point(fire_point(0, 0));
+        cluster.add_fire_point(fire_point(1, 0));
+
+        let expected = cluster.pixels()[0].geographic_area_m2() * 2.0;
+        assert!((cluster.geographic_area_m2() - expected).abs() < 1.0);
+
+        // A 1 degree by 1 degree pixel at the equator is about 111 km on a side.
+        let km2 = cluster.geographic_area_m2() / 1.0e6;
+        assert!((km2 - 2.0 * 111.2 * 111.2).abs() < 50.0, "{}", km2);
+    }
+
+    #[test]
+    fn test_cluster_merge() {
+        let mut cluster = Cluster::default();
+        cluster.add_fire_point(fire_point(0, 0));
+        cluster.add_fire_point(fire_point(1, 0));
+
+        let mut other = Cluster::default();
+        let mut hot = fire_point(1, 0);
+        hot.pixel.power = 3.0;
+        hot.pixel.temperature = 900.0;
+        other.add_fire_point(hot);
+        let mut far = fire_point(2, 0);
+        far.pixel.scan_angle = 5.0;
+        other.add_fire_point(far);
+
+        cluster.merge(&other);
+
+        assert_eq!(cluster.pixel_count(), 3);
+        assert_eq!(cluster.total_power(), 5.0);
+        assert_eq!(cluster.total_area(), 3.0);
+        assert_eq!(cluster.max_temperature(), 900.0);
+        assert_eq!(cluster.max_scan_angle(), 5.0);
+    }
+
+    #[test]
+    fn test_nearest_cluster() {
+        let cluster_at = |x, y| {
+            let mut cluster = Cluster::default();
+            cluster.add_fire_point(fire_point(x, y));
+            cluster
+        };
+
+        let start = DateTime::<Utc>::from_utc(
+            chrono::NaiveDate::from_ymd(2022, 7, 4).and_hms(18, 0, 0),
+            Utc,
+        );
+        let mut clist = ClusterList::new(
+            Satellite::G17,
+            Sector::FULL,
+            start,
+            start,
+            vec![cluster_at(0, 0), cluster_at(10, 0), cluster_at(0, 10)],
+        );
+
+        let (nearest, dist) = clist
+            .nearest_cluster(Coord {
+                lat: -1.0,
+                lon: 10.0,
+            })
+            .unwrap();
+        assert!(std::ptr::eq(nearest, &clist.clusters()[1]));
+        // Half a degree of latitude and longitude away at the equator.
+        assert!((dist - 78_600.0).abs() < 500.0, "{}", dist);
+
+        clist.filter(|_| false);
+        assert!(clist
+            .nearest_cluster(Coord { lat: 0.0, lon: 0.0 })
+            .is_none());
+    }
 
-            let mut adjacent = false;
-            for cluster_point in &cluster_points {
-                let dx = (cluster_point.x - candidate.x).abs();
-                let dy = (cluster_point.y - candidate.y).abs();
+    #[test]
+    fn test_clusters_from_fire_points() {
+        // Points are in row major order like they come out of the image.
+        //
+        //   X . X . . X
+        //   X X X . . .
+        //   . . . . X .
+        //
+        let points = vec![
+            fire_point(0, 0),
+            fire_point(2, 0),
+            fire_point(5, 0),
+            fire_point(0, 1),
+            fire_point(1, 1),
+            fire_point(2, 1),
+            fire_point(4, 2),
+        ];
+
+        let clusters = clusters_from_fire_points(points, Connectivity::Eight);
+
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0].pixel_count(), 5);
+        assert_eq!(clusters[1].pixel_count(), 1);
+        assert_eq!(clusters[2].pixel_count(), 1);
+
+        assert_eq!(clusters[0].total_power(), 5.0);
+        assert_eq!(clusters[1].pixels()[0].ul.lon, 5.0);
+        assert_eq!(clusters[2].pixels()[0].ul.lon, 4.0);
+    }
 
-                if dx <= 1 && dy <= 1 {
-                    adjacent = true;
-                    break;
+    #[test]
+    fn test_clusters_connectivity() {
+        // An L shape and a diagonal line.
+        //
+        //   X . . . X
+        //   X . . X .
+        //   X X . . .
+        //
+        let points = vec![
+            fire_point(0, 0),
+            fire_point(4, 0),
+            fire_point(0, 1),
+            fire_point(3, 1),
+            fire_point(0, 2),
+            fire_point(1, 2),
+        ];
+
+        let clusters = clusters_from_fire_points(points.clone(), Connectivity::Eight);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].pixel_count(), 4);
+        assert_eq!(clusters[1].pixel_count(), 2);
+
+        let clusters = clusters_from_fire_points(points.clone(), Connectivity::Four);
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0].pixel_count(), 4);
+        assert_eq!(clusters[1].pixel_count(), 1);
+        assert_eq!(clusters[2].pixel_count(), 1);
+
+        for connectivity in [Connectivity::Four, Connectivity::Eight] {
+            let expected = clusters_from_fire_points(points.clone(), connectivity);
+            let clusters = clusters_from_fire_points_parallel(points.clone(), 3, connectivity);
+            assert_eq!(clusters.len(), expected.len());
+        }
+    }
+
+    #[test]
+    fn test_clusters_from_fire_points_parallel() {
+        // A pseudo-random scattering of points with lots of clusters of different shapes.
+        let mut state: u32 = 12345;
+        let mut points = vec![];
+        for y in 0..97 {
+            for x in 0..50 {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                if (state >> 16).is_multiple_of(3) {
+                    points.push(fire_point(x, y));
                 }
             }
+        }
 
-            if adjacent {
-                cluster_points.push(*candidate);
-                candidate.x = isize::MIN;
-                candidate.y = isize::MIN;
+        let expected = clusters_from_fire_points(points.clone(), Connectivity::Eight);
+
+        for num_tiles in [1, 2, 3, 7, 200] {
+            let clusters =
+                clusters_from_fire_points_parallel(points.clone(), num_tiles, Connectivity::Eight);
+
+            assert_eq!(clusters.len(), expected.len(), "{}", num_tiles);
+            for (cluster, expected) in clusters.iter().zip(&expected) {
+                assert_eq!(cluster.pixel_count(), expected.pixel_count());
+                assert_eq!(cluster.pixels()[0].ul.lon, expected.pixels()[0].ul.lon);
+                assert_eq!(cluster.pixels()[0].ul.lat, expected.pixels()[0].ul.lat);
             }
         }
+    }
+
+    #[test]
+    fn test_split_into_bands() {
+        // Two rows of points far apart, with lots of empty rows in between.
+        let points = vec![
+            fire_point(0, 10),
+            fire_point(1, 10),
+            fire_point(0, 50),
+            fire_point(1, 50),
+        ];
+
+        for num_tiles in [2, 3, 7, 200] {
+            let (_, bands) = split_into_bands(&points, 10, num_tiles);
+            assert_eq!(bands, vec![vec![0, 1], vec![2, 3]], "{}", num_tiles);
+        }
 
-        let mut curr_clust = Cluster::default();
-        cluster_points
-            .drain(..)
-            .for_each(|cp| curr_clust.add_fire_point(cp));
-        clusters.push(curr_clust);
+        let (band_height, bands) = split_into_bands(&points, 10, 1);
+        assert_eq!(band_height, 41);
+        assert_eq!(bands, vec![vec![0, 1, 2, 3]]);
     }
 
-    clusters
+    #[test]
+    fn test_clusters_from_fire_points_parallel_straddles_tile_edge() {
+        // With 2 tiles rows 0-1 are in the first band and rows 2-3 are in the second.
+        //
+        //   X . . .
+        //   . X . X
+        //   X . X .
+        //   X . . .
+        //
+        let points = vec![
+            fire_point(0, 0),
+            fire_point(1, 1),
+            fire_point(3, 1),
+            fire_point(0, 2),
+            fire_point(2, 2),
+            fire_point(0, 3),
+        ];
+
+        let clusters = clusters_from_fire_points_parallel(points, 2, Connectivity::Eight);
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].pixel_count(), 6);
+    }
+
+    /// A solid block of points next to a grid of isolated points, size x size in all.
+    fn dense_fire_points(size: isize) -> Vec<FirePoint> {
+        (0..size)
+            .flat_map(|y| (0..size).map(move |x| (x, y)))
+            .filter(|&(x, y)| x < size / 2 || (x % 2 == 1 && y % 2 == 0))
+            .map(|(x, y)| fire_point(x, y))
+            .collect()
+    }
+
+    /// The clustering algorithm used before the union-find, kept to compare against.
+    ///
+    /// Each point starts a cluster and pulls in every later point next to a point already in it,
+    /// checking all the points in the cluster for every candidate.
+    fn clusters_from_fire_points_quadratic(points: Vec<FirePoint>) -> Vec<Cluster> {
+        let mut clusters: Vec<Cluster> = vec![];
+        let mut used = vec![false; points.len()];
+
+        for i in 0..points.len() {
+            if used[i] {
+                continue;
+            }
+            used[i] = true;
+
+            let mut cluster_points = vec![points[i]];
+            for j in (i + 1)..points.len() {
+                let candidate = points[j];
+                let adjacent = cluster_points.iter().any(|cluster_point| {
+                    (cluster_point.x - candidate.x).abs() <= 1
+                        && (cluster_point.y - candidate.y).abs() <= 1
+                });
+
+                if !used[j] && adjacent {
+                    used[j] = true;
+                    cluster_points.push(candidate);
+                }
+            }
+
+            let mut cluster = Cluster::default();
+            cluster_points
+                .into_iter()
+                .for_each(|cp| cluster.add_fire_point(cp));
+            clusters.push(cluster);
+        }
+
+        clusters
+    }
+
+    #[test]
+    fn test_clusters_from_dense_fire_points() {
+        const SIZE: isize = 300;
+
+        let points = dense_fire_points(SIZE);
+        let num_points = points.len();
+
+        let clusters = clusters_from_fire_points(points, Connectivity::Eight);
+
+        let isolated = (SIZE / 4) * (SIZE / 2);
+        assert_eq!(clusters.len(), 1 + isolated as usize);
+        assert_eq!(clusters[0].pixel_count(), num_points - isolated as usize);
+    }
+
+    /// Time the union-find against the old quadratic algorithm on growing dense grids.
+    ///
+    /// Run it with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_clusters_from_dense_fire_points() {
+        use std::time::Instant;
+
+        for size in [50, 100, 200] {
+            let points = dense_fire_points(size);
+            let num_points = points.len();
+
+            let start = Instant::now();
+            let old = clusters_from_fire_points_quadratic(points.clone());
+            let old_elapsed = start.elapsed();
+
+            let start = Instant::now();
+            let new = clusters_from_fire_points(points, Connectivity::Eight);
+            let new_elapsed = start.elapsed();
+
+            assert_eq!(old.len(), new.len());
+            assert!(old
+                .iter()
+                .zip(&new)
+                .all(|(o, n)| o.pixel_count() == n.pixel_count()));
+
+            println!(
+                "{:6} points: old {:>12?} new {:>12?}",
+                num_points, old_elapsed, new_elapsed
+            );
+        }
+    }
 }
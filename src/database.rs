@@ -2,15 +2,168 @@ use crate::{
     cluster::ClusterList,
     fire::{Fire, FireList},
     geo::{BoundingBox, Coord, Geo},
+    json_number,
     pixel::PixelList,
     satellite::{Satellite, Sector},
     SatFireResult,
 };
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use log::{info, warn};
-use rusqlite::{Connection, OpenFlags, ToSql};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, ToSql};
 use rustc_hash::FxHashMap as HashMap;
-use std::path::Path;
+use std::{
+    fmt::{self, Display},
+    io::Write,
+    path::Path,
+};
+
+/// One step in the history of a database schema.
+struct Migration {
+    /// The SQL to update the schema.
+    sql: &'static str,
+    /// Fill in any data the new schema needs from the data already in the database.
+    backfill: Option<fn(&Connection) -> SatFireResult<()>>,
+}
+
+/// The schema of the cluster database, one step for each version.
+///
+/// A database at version N has had the first N steps applied. Never change a step once it has been
+/// released, add a new one instead.
+const CLUSTER_DB_MIGRATIONS: &[Migration] = &[
+    Migration {
+        sql: include_str!("database/create_cluster_db.sql"),
+        backfill: None,
+    },
+    Migration {
+        sql: include_str!("database/create_cluster_rtree.sql"),
+        backfill: Some(backfill_spatial_index),
+    },
+];
+
+/// The schema of the fires database, one step for each version.
+///
+/// See [CLUSTER_DB_MIGRATIONS].
+const FIRE_DB_MIGRATIONS: &[Migration] = &[Migration {
+    sql: include_str!("database/create_fire_db.sql"),
+    backfill: None,
+}];
+
+/// How hard SQLite works to make sure a committed transaction survives a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+    /// SQLite's synchronous=NORMAL, the default for connections from this library.
+    ///
+    /// In WAL mode the database can't be corrupted by a crash, but the most recent transactions
+    /// may be rolled back after a power failure or operating system crash. They are never lost if
+    /// only the program crashes. Since an interrupted run can just be run again, this is usually
+    /// the right trade for the much faster commits.
+    #[default]
+    Normal,
+    /// SQLite's synchronous=FULL, every commit is synced to disk before it returns.
+    Full,
+}
+
+impl Durability {
+    fn pragma_value(self) -> &'static str {
+        match self {
+            Durability::Normal => "NORMAL",
+            Durability::Full => "FULL",
+        }
+    }
+}
+
+/// Set the pragmas used for all connections.
+///
+/// The write-ahead log lets the threads that query a database keep going while another thread
+/// commits to it. Note that WAL mode doesn't work with databases on a network file system.
+fn configure_connection(conn: &Connection) -> SatFireResult<()> {
+    // This only takes effect for a new database, so the space freed by pruning old data can be
+    // returned to the file system without a full VACUUM. It must come before anything else.
+    conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")?;
+    // The journal mode is stored in the database file, so this really only matters the first time.
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.pragma_update(None, "synchronous", Durability::default().pragma_value())?;
+    // A negative value is in KiB, so this is 64 MiB.
+    conn.pragma_update(None, "cache_size", -65_536)?;
+
+    Ok(())
+}
+
+/// Bring the schema of a database up to date.
+///
+/// The schema version is stored in the meta table. Databases created before there was a version
+/// are treated as version 0, and since the first step only creates tables that don't exist yet it
+/// is safe to apply to them.
+fn migrate(conn: &Connection, migrations: &[Migration]) -> SatFireResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value INTEGER NOT NULL)",
+    )?;
+
+    // Most of the time there is nothing to do, so don't take the write lock.
+    if schema_version(conn)? == migrations.len() {
+        return Ok(());
+    }
+
+    // Take the write lock before checking the version again so two connections don't both migrate.
+    conn.execute("BEGIN IMMEDIATE TRANSACTION", [])?;
+
+    // Each step and its backfill are applied in the same transaction, so a database at a version
+    // always has all the data that version needs.
+    match apply_migrations(conn, migrations) {
+        Ok(()) => {
+            conn.execute("COMMIT", [])?;
+            Ok(())
+        }
+        Err(err) => {
+            // The error from the migration is the one worth reporting.
+            let _ = conn.execute("ROLLBACK", []);
+            Err(err)
+        }
+    }
+}
+
+fn schema_version(conn: &Connection) -> SatFireResult<usize> {
+    let version = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'schema_version'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|version| version as usize)
+        .or_else(|err| match err {
+            rusqlite::Error::QueryReturnedNoRows => Ok(0),
+            err => Err(err),
+        })?;
+
+    Ok(version)
+}
+
+fn apply_migrations(conn: &Connection, migrations: &[Migration]) -> SatFireResult<()> {
+    let version = schema_version(conn)?;
+
+    if version > migrations.len() {
+        return Err(format!(
+            "database schema version {} is newer than the supported version {}",
+            version,
+            migrations.len()
+        )
+        .into());
+    }
+
+    for (i, migration) in migrations.iter().enumerate().skip(version) {
+        conn.execute_batch(migration.sql)?;
+        if let Some(backfill) = migration.backfill {
+            backfill(conn)?;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('schema_version', ?)",
+            [i as i64 + 1],
+        )?;
+        info!("Database schema migrated to version {}", i + 1);
+    }
+
+    Ok(())
+}
 
 /// Represents a connection to the database where ALL the information related to fires is stored.
 pub struct ClusterDatabase {
@@ -26,7 +179,33 @@ impl ClusterDatabase {
     pub fn initialize<P: AsRef<Path>>(path: P) -> SatFireResult<()> {
         let path = path.as_ref();
 
-        let _conn = Self::open_database_to_write(path)?;
+        let conn = Self::open_database_to_write(path)?;
+        Self::update_spatial_index(&conn)?;
+        Ok(())
+    }
+
+    /// Add any clusters missing from the spatial index and remove index entries for clusters that
+    /// no longer exist. The migration fills the index, so this only repairs a database that was
+    /// changed by an older version of the programs that didn't know about the index.
+    fn update_spatial_index(conn: &Connection) -> SatFireResult<()> {
+        const ORPHANS_QUERY: &str = r#"
+            DELETE FROM clusters_rtree
+            WHERE cluster_id NOT IN (SELECT cluster_id FROM clusters)"#;
+
+        conn.execute("BEGIN TRANSACTION", [])?;
+
+        let num_added = add_missing_to_spatial_index(conn)?;
+        let num_removed = conn.execute(ORPHANS_QUERY, [])?;
+
+        conn.execute("COMMIT", [])?;
+
+        if num_added > 0 || num_removed > 0 {
+            info!(
+                "Spatial index updated, added {} clusters and removed {}",
+                num_added, num_removed
+            );
+        }
+
         Ok(())
     }
 
@@ -48,12 +227,19 @@ impl ClusterDatabase {
 
         // A 5-second busy time out is WAY too much. If we hit this something has gone terribly wrong.
         conn.busy_timeout(std::time::Duration::from_secs(5))?;
-        const QUERY: &str = include_str!("database/create_cluster_db.sql");
-        conn.execute_batch(QUERY)?;
+        configure_connection(&conn)?;
+        migrate(&conn, CLUSTER_DB_MIGRATIONS)?;
 
         Ok(conn)
     }
 
+    /// Set the durability of commits for this connection, [Durability::Normal] by default.
+    pub fn set_durability(&self, durability: Durability) -> SatFireResult<()> {
+        self.conn
+            .pragma_update(None, "synchronous", durability.pragma_value())?;
+        Ok(())
+    }
+
     /// Find the latest valid time in the database so you can safely skip anything older.
     pub fn newest_scan_start(
         &self,
@@ -61,7 +247,7 @@ impl ClusterDatabase {
         sector: Sector,
     ) -> SatFireResult<DateTime<Utc>> {
         const QUERY: &str = include_str!("database/query_newest_cluster.sql");
-        let mut stmt = self.conn.prepare(QUERY)?;
+        let mut stmt = self.conn.prepare_cached(QUERY)?;
 
         let res: DateTime<Utc> = stmt.query_row(
             [
@@ -81,16 +267,91 @@ impl ClusterDatabase {
         Ok(res)
     }
 
+    /// Summarize what data is in the database.
+    ///
+    /// There is one entry for each satellite and sector with any clusters in the database. Scans
+    /// that were processed but had no clusters are not included.
+    pub fn coverage_summary(&self) -> SatFireResult<Vec<CoverageEntry>> {
+        const QUERY: &str = include_str!("database/query_coverage.sql");
+        let mut stmt = self.conn.prepare_cached(QUERY)?;
+
+        let rows = stmt.query_and_then([], |row| -> SatFireResult<CoverageEntry> {
+            let satellite: Satellite = row.get::<_, String>(0)?.parse()?;
+            let sector: Sector = row.get::<_, String>(1)?.parse()?;
+            let earliest_scan: DateTime<Utc> =
+                DateTime::from_utc(chrono::NaiveDateTime::from_timestamp(row.get(2)?, 0), Utc);
+            let latest_scan: DateTime<Utc> =
+                DateTime::from_utc(chrono::NaiveDateTime::from_timestamp(row.get(3)?, 0), Utc);
+            let cluster_count = u64::try_from(row.get::<_, i64>(4)?)?;
+
+            Ok(CoverageEntry {
+                satellite,
+                sector,
+                earliest_scan,
+                latest_scan,
+                cluster_count,
+            })
+        })?;
+
+        rows.collect()
+    }
+
+    /// Delete all the clusters from scans that ended before `cutoff`.
+    ///
+    /// Returns the number of clusters deleted. The records of scans with no clusters from before
+    /// the cutoff are deleted as well, and so are the spatial index entries. Afterwards the free
+    /// pages are returned to the file system if the database was created with incremental auto
+    /// vacuum, which is the default for databases created by this library.
+    pub fn delete_before(&self, cutoff: DateTime<Utc>) -> SatFireResult<usize> {
+        const DELETE_RTREE_QUERY: &str = r#"
+            DELETE FROM clusters_rtree
+            WHERE cluster_id IN (SELECT cluster_id FROM clusters WHERE end_time < ?)"#;
+        const DELETE_CLUSTERS_QUERY: &str = "DELETE FROM clusters WHERE end_time < ?";
+        const DELETE_NO_CLUSTERS_QUERY: &str = "DELETE FROM no_clusters WHERE end_time < ?";
+
+        let cutoff = cutoff.timestamp();
+
+        self.conn.execute("BEGIN TRANSACTION", [])?;
+
+        let res = (|| -> SatFireResult<usize> {
+            self.conn.execute(DELETE_RTREE_QUERY, [cutoff])?;
+            let num_deleted = self.conn.execute(DELETE_CLUSTERS_QUERY, [cutoff])?;
+            self.conn.execute(DELETE_NO_CLUSTERS_QUERY, [cutoff])?;
+            Ok(num_deleted)
+        })();
+
+        let num_deleted = match res {
+            Ok(num_deleted) => {
+                self.conn.execute("COMMIT", [])?;
+                num_deleted
+            }
+            Err(err) => {
+                if let Err(rollback_err) = self.conn.execute("ROLLBACK", []) {
+                    warn!("error rolling back failed delete: {}", rollback_err);
+                }
+                return Err(err);
+            }
+        };
+
+        // This does nothing unless auto_vacuum is INCREMENTAL.
+        self.conn.execute_batch("PRAGMA incremental_vacuum")?;
+
+        Ok(num_deleted)
+    }
+
     /// Prepare to add cluster rows to the database.
-    pub fn prepare_to_add_clusters(&self) -> SatFireResult<ClusterDatabaseAddCluster> {
+    pub fn prepare_to_add_clusters(&self) -> SatFireResult<ClusterDatabaseAddCluster<'_>> {
         const ADD_CLUSTER_QUERY: &str = include_str!("database/add_cluster.sql");
+        const ADD_RTREE_QUERY: &str = include_str!("database/add_cluster_rtree.sql");
         const ADD_NO_FIRE_QUERY: &str = include_str!("database/add_no_cluster.sql");
 
-        let add_cluster_stmt = self.conn.prepare(ADD_CLUSTER_QUERY)?;
-        let add_no_fire_stmt = self.conn.prepare(ADD_NO_FIRE_QUERY)?;
+        let add_cluster_stmt = self.conn.prepare_cached(ADD_CLUSTER_QUERY)?;
+        let add_rtree_stmt = self.conn.prepare_cached(ADD_RTREE_QUERY)?;
+        let add_no_fire_stmt = self.conn.prepare_cached(ADD_NO_FIRE_QUERY)?;
 
         Ok(ClusterDatabaseAddCluster {
             add_cluster_stmt,
+            add_rtree_stmt,
             add_no_fire_stmt,
             conn: &self.conn,
         })
@@ -99,12 +360,12 @@ impl ClusterDatabase {
     /// Prepare to query the database if data from a satellite image is already in the database.
     pub fn prepare_to_query_clusters_present(
         &self,
-    ) -> SatFireResult<ClusterDatabaseQueryClusterPresent> {
+    ) -> SatFireResult<ClusterDatabaseQueryClusterPresent<'_>> {
         const QUERY_CLUSTER: &str = include_str!("database/query_num_clusters_present.sql");
         const QUERY_NO_FIRE: &str = include_str!("database/query_no_clusters.sql");
 
-        let clusters_stmt = self.conn.prepare(QUERY_CLUSTER)?;
-        let no_fire_stmt = self.conn.prepare(QUERY_NO_FIRE)?;
+        let clusters_stmt = self.conn.prepare_cached(QUERY_CLUSTER)?;
+        let no_fire_stmt = self.conn.prepare_cached(QUERY_NO_FIRE)?;
 
         Ok(ClusterDatabaseQueryClusterPresent {
             clusters_stmt,
@@ -113,6 +374,15 @@ impl ClusterDatabase {
     }
 
     /// Query clusters from the database.
+    ///
+    /// The spatial index narrows the search to clusters with a bounding box that overlaps `area`
+    /// before checking if the centroid is inside `area`. If `min_power` is given, only clusters
+    /// with at least that much power (MW) are returned. That filter is done in the database, so
+    /// the pixels of small clusters are never loaded.
+    ///
+    /// The rows are read from the database as the iterator from
+    /// [rows](ClusterDatabaseQueryClusters::rows) advances, so only one cluster is held in memory
+    /// at a time no matter how many clusters match.
     pub fn query_clusters(
         &self,
         sat: Option<Satellite>,
@@ -120,59 +390,252 @@ impl ClusterDatabase {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
         area: BoundingBox,
+        min_power: Option<f64>,
     ) -> SatFireResult<ClusterDatabaseQueryClusters<'_>> {
-        let sat_select = if let Some(sat) = sat {
-            format!("AND satellite = '{}'", sat.name())
-        } else {
-            String::new()
-        };
+        let query = format!(
+            "{} ORDER BY start_time ASC",
+            clusters_query_sql(sat, sect, start, end, area, min_power)
+        );
 
-        let sector_select = if let Some(sect) = sect {
-            format!("AND sector = '{}'", sect.name())
+        let stmt = self.conn.prepare(&query)?;
+
+        Ok(ClusterDatabaseQueryClusters { stmt, min_power })
+    }
+
+    /// Export the clusters from a query as CSV, one row per cluster.
+    ///
+    /// The arguments select the clusters the same way as
+    /// [query_clusters](ClusterDatabase::query_clusters). The output starts with a header row and
+    /// follows RFC 4180, so records end with CRLF and fields are quoted when needed.
+    pub fn export_csv<W: Write>(
+        &self,
+        sat: Option<Satellite>,
+        sect: Option<Sector>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        area: BoundingBox,
+        w: &mut W,
+    ) -> SatFireResult<()> {
+        write_csv_record(w, &EXPORT_FIELDS)?;
+
+        let mut query = self.query_clusters(sat, sect, start, end, area, None)?;
+        for row in query.rows()? {
+            let row = row?;
+
+            write_csv_record(
+                w,
+                &[
+                    row.sat.name(),
+                    row.sector.name(),
+                    &row.start.to_rfc3339(),
+                    &row.end.to_rfc3339(),
+                    &row.centroid.lat.to_string(),
+                    &row.centroid.lon.to_string(),
+                    &row.power.to_string(),
+                    &row.area.to_string(),
+                    &row.max_temperature.to_string(),
+                    &row.scan_angle.to_string(),
+                    &row.pixels.len().to_string(),
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Export the clusters from a query as newline delimited JSON, one object per line.
+    ///
+    /// The arguments select the clusters the same way as
+    /// [query_clusters](ClusterDatabase::query_clusters), and each object has the same fields as
+    /// the columns from [export_csv](ClusterDatabase::export_csv). Times are RFC 3339 strings, and
+    /// any numbers that aren't finite are written as `null`.
+    pub fn write_ndjson<W: Write>(
+        &self,
+        sat: Option<Satellite>,
+        sect: Option<Sector>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        area: BoundingBox,
+        w: &mut W,
+    ) -> SatFireResult<()> {
+        let mut query = self.query_clusters(sat, sect, start, end, area, None)?;
+        for row in query.rows()? {
+            let row = row?;
+
+            // None of the strings need to be escaped, they are names and times.
+            let strings = [
+                row.sat.name(),
+                row.sector.name(),
+                &row.start.to_rfc3339(),
+                &row.end.to_rfc3339(),
+            ];
+            let numbers = [
+                row.centroid.lat,
+                row.centroid.lon,
+                row.power,
+                row.area,
+                row.max_temperature,
+                row.scan_angle,
+            ];
+
+            let (string_fields, rest) = EXPORT_FIELDS.split_at(strings.len());
+            let (number_fields, count_field) = rest.split_at(numbers.len());
+
+            w.write_all(b"{")?;
+            for (name, val) in string_fields.iter().zip(strings) {
+                write!(w, "\"{}\":\"{}\",", name, val)?;
+            }
+            for (name, val) in number_fields.iter().zip(numbers) {
+                write!(w, "\"{}\":{},", name, json_number(val))?;
+            }
+            writeln!(w, "\"{}\":{}}}", count_field[0], row.pixels.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The fields for each cluster written by [ClusterDatabase::export_csv] and
+/// [ClusterDatabase::write_ndjson], in order.
+const EXPORT_FIELDS: [&str; 11] = [
+    "satellite",
+    "sector",
+    "start",
+    "end",
+    "lat",
+    "lon",
+    "power_mw",
+    "area_m2",
+    "max_temperature_k",
+    "max_scan_angle",
+    "pixel_count",
+];
+
+/// Write a CSV record, quoting any fields that contain a comma, quote, or line break.
+fn write_csv_record<W: Write>(w: &mut W, fields: &[&str]) -> std::io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+
+        if field.contains(&[',', '"', '\r', '\n'][..]) {
+            write!(w, "\"{}\"", field.replace('"', "\"\""))?;
         } else {
-            String::new()
-        };
+            w.write_all(field.as_bytes())?;
+        }
+    }
 
-        let query = &format!(
-            r#"SELECT
-                 rowid,
-                 satellite,
-                 sector,
-                 start_time,
-                 end_time,
-                 power,
-                 max_temperature,
-                 area,
-                 max_scan_angle,
-                 lat,
-                 lon,
-                 pixels
-               FROM clusters
-               WHERE
-                 start_time >= {} AND
-                 end_time <= {} AND
-                 lat >= {} AND lat <= {} AND
-                 lon >= {} AND lon <= {} {} {}
-               ORDER BY start_time ASC"#,
-            start.timestamp(),
-            end.timestamp(),
-            area.ll.lat,
-            area.ur.lat,
-            area.ll.lon,
-            area.ur.lon,
-            sat_select,
-            sector_select
-        );
+    w.write_all(b"\r\n")
+}
 
-        let stmt = self.conn.prepare(query)?;
+/// Add the bounding box of every cluster that isn't in the spatial index yet.
+///
+/// Returns the number of clusters added. This doesn't start a transaction, so the caller should.
+fn add_missing_to_spatial_index(conn: &Connection) -> SatFireResult<usize> {
+    const MISSING_QUERY: &str = r#"
+        SELECT cluster_id, pixels FROM clusters
+        WHERE cluster_id NOT IN (SELECT cluster_id FROM clusters_rtree)"#;
+    const ADD_RTREE_QUERY: &str = include_str!("database/add_cluster_rtree.sql");
+
+    let mut missing_stmt = conn.prepare(MISSING_QUERY)?;
+    let mut add_rtree_stmt = conn.prepare(ADD_RTREE_QUERY)?;
+
+    let mut num_added = 0;
+    let mut rows = missing_stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let cluster_id: i64 = row.get(0)?;
+        let pixels = match row.get_ref(1)? {
+            rusqlite::types::ValueRef::Blob(bytes) => {
+                let mut cursor = std::io::Cursor::new(bytes);
+                PixelList::binary_deserialize(&mut cursor)?
+            }
+            _ => return Err("Invalid type in pixels column".into()),
+        };
 
-        Ok(ClusterDatabaseQueryClusters { stmt })
+        add_rtree_row(&mut add_rtree_stmt, cluster_id, &pixels)?;
+        num_added += 1;
     }
+
+    Ok(num_added)
+}
+
+/// Fill the new spatial index with the clusters already in the database.
+fn backfill_spatial_index(conn: &Connection) -> SatFireResult<()> {
+    let num_added = add_missing_to_spatial_index(conn)?;
+    info!("Added {} clusters to the spatial index", num_added);
+    Ok(())
+}
+
+/// Build the SQL to select clusters for a query, without any ORDER BY clause.
+fn clusters_query_sql(
+    sat: Option<Satellite>,
+    sect: Option<Sector>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    area: BoundingBox,
+    min_power: Option<f64>,
+) -> String {
+    let sat_select = if let Some(sat) = sat {
+        format!("AND satellite = '{}'", sat.name())
+    } else {
+        String::new()
+    };
+
+    let sector_select = if let Some(sect) = sect {
+        format!("AND sector = '{}'", sect.name())
+    } else {
+        String::new()
+    };
+
+    // The minimum power is bound as a parameter when the query is run.
+    let power_select = if min_power.is_some() {
+        "AND power >= ?"
+    } else {
+        ""
+    };
+
+    format!(
+        r#"SELECT
+             clusters.cluster_id,
+             satellite,
+             sector,
+             start_time,
+             end_time,
+             power,
+             max_temperature,
+             area,
+             max_scan_angle,
+             lat,
+             lon,
+             pixels
+           FROM clusters JOIN clusters_rtree ON clusters.cluster_id = clusters_rtree.cluster_id
+           WHERE
+             clusters_rtree.max_lat >= {} AND clusters_rtree.min_lat <= {} AND
+             clusters_rtree.max_lon >= {} AND clusters_rtree.min_lon <= {} AND
+             start_time >= {} AND
+             end_time <= {} AND
+             lat >= {} AND lat <= {} AND
+             lon >= {} AND lon <= {} {} {} {}"#,
+        area.ll.lat,
+        area.ur.lat,
+        area.ll.lon,
+        area.ur.lon,
+        start.timestamp(),
+        end.timestamp(),
+        area.ll.lat,
+        area.ur.lat,
+        area.ll.lon,
+        area.ur.lon,
+        sat_select,
+        sector_select,
+        power_select
+    )
 }
 
 pub struct ClusterDatabaseAddCluster<'a> {
-    add_cluster_stmt: rusqlite::Statement<'a>,
-    add_no_fire_stmt: rusqlite::Statement<'a>,
+    add_cluster_stmt: rusqlite::CachedStatement<'a>,
+    add_rtree_stmt: rusqlite::CachedStatement<'a>,
+    add_no_fire_stmt: rusqlite::CachedStatement<'a>,
     conn: &'a Connection,
 }
 
@@ -187,8 +650,26 @@ impl<'a> ClusterDatabaseAddCluster<'a> {
     }
 
     fn add_clusters(&mut self, clist: ClusterList) -> SatFireResult<()> {
+        // Each image is added in a single transaction so an interrupted run never leaves part of
+        // an image in the database, and a resumed run will process the whole image again.
         self.conn.execute("BEGIN TRANSACTION", [])?;
 
+        match self.insert_clusters(clist) {
+            Ok(()) => {
+                self.conn.execute("COMMIT", [])?;
+                Ok(())
+            }
+            Err(err) => {
+                // Don't leave the transaction open, or it would be committed with the next image.
+                if let Err(rollback_err) = self.conn.execute("ROLLBACK", []) {
+                    warn!("error rolling back failed cluster insert: {}", rollback_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn insert_clusters(&mut self, clist: ClusterList) -> SatFireResult<()> {
         let satellite = clist.satellite();
         let sector = clist.sector();
         let scan_start = clist.scan_start().timestamp();
@@ -215,9 +696,10 @@ impl<'a> ClusterDatabaseAddCluster<'a> {
                 &angle,
                 &pixels,
             ])?;
-        }
 
-        self.conn.execute("COMMIT", [])?;
+            let cluster_id = self.conn.last_insert_rowid();
+            add_rtree_row(&mut self.add_rtree_stmt, cluster_id, cluster.pixels())?;
+        }
 
         Ok(())
     }
@@ -239,9 +721,27 @@ impl<'a> ClusterDatabaseAddCluster<'a> {
     }
 }
 
+/// Add the bounding box of a cluster to the spatial index.
+fn add_rtree_row(
+    stmt: &mut rusqlite::Statement,
+    cluster_id: i64,
+    pixels: &PixelList,
+) -> SatFireResult<()> {
+    let BoundingBox { ll, ur } = pixels.bounding_box();
+    stmt.execute([
+        &cluster_id as &dyn ToSql,
+        &ll.lat,
+        &ur.lat,
+        &ll.lon,
+        &ur.lon,
+    ])?;
+
+    Ok(())
+}
+
 pub struct ClusterDatabaseQueryClusterPresent<'a> {
-    clusters_stmt: rusqlite::Statement<'a>,
-    no_fire_stmt: rusqlite::Statement<'a>,
+    clusters_stmt: rusqlite::CachedStatement<'a>,
+    no_fire_stmt: rusqlite::CachedStatement<'a>,
 }
 
 impl<'a> ClusterDatabaseQueryClusterPresent<'a> {
@@ -290,6 +790,7 @@ impl<'a> ClusterDatabaseQueryClusterPresent<'a> {
 
 pub struct ClusterDatabaseQueryClusters<'a> {
     stmt: rusqlite::Statement<'a>,
+    min_power: Option<f64>,
 }
 
 impl<'a> ClusterDatabaseQueryClusters<'a> {
@@ -297,7 +798,8 @@ impl<'a> ClusterDatabaseQueryClusters<'a> {
     pub fn rows(
         &mut self,
     ) -> SatFireResult<impl Iterator<Item = SatFireResult<ClusterDatabaseClusterRow>> + '_> {
-        Ok(self.stmt.query_and_then([], query_row_to_cluster_row)?)
+        let params = rusqlite::params_from_iter(self.min_power.iter());
+        Ok(self.stmt.query_and_then(params, query_row_to_cluster_row)?)
     }
 }
 
@@ -319,6 +821,32 @@ pub struct ClusterDatabaseClusterRow {
 
 impl ClusterDatabaseClusterRow {}
 
+/// A summary of the clusters in a database for one satellite and sector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageEntry {
+    pub satellite: Satellite,
+    pub sector: Sector,
+    /// The start time of the earliest scan with a cluster.
+    pub earliest_scan: DateTime<Utc>,
+    /// The start time of the latest scan with a cluster.
+    pub latest_scan: DateTime<Utc>,
+    pub cluster_count: u64,
+}
+
+impl Display for CoverageEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} {:<5} {} to {} ({} clusters)",
+            self.satellite.name(),
+            self.sector.name(),
+            self.earliest_scan.format("%Y-%m-%d %H:%M:%S"),
+            self.latest_scan.format("%Y-%m-%d %H:%M:%S"),
+            self.cluster_count
+        )
+    }
+}
+
 /// Represents a connection to the database where ALL the information related to fires is stored.
 pub struct FiresDatabase {
     conn: Connection,
@@ -355,30 +883,47 @@ impl FiresDatabase {
 
         // A 5-second busy time out is WAY too much. If we hit this something has gone terribly wrong.
         conn.busy_timeout(std::time::Duration::from_secs(5))?;
-        const QUERY: &str = include_str!("database/create_fire_db.sql");
-        conn.execute_batch(QUERY)?;
+        configure_connection(&conn)?;
+        migrate(&conn, FIRE_DB_MIGRATIONS)?;
 
         Ok(conn)
     }
 
+    /// Set the durability of commits for this connection, [Durability::Normal] by default.
+    pub fn set_durability(&self, durability: Durability) -> SatFireResult<()> {
+        self.conn
+            .pragma_update(None, "synchronous", durability.pragma_value())?;
+        Ok(())
+    }
+
     /// Get the next id number for a wildfire.
     pub fn next_wildfire_id(&self) -> SatFireResult<u64> {
         const QUERY: &str = "SELECT IFNULL(MAX(fire_id) + 1, 1) FROM fires";
 
-        let mut stmt = self.conn.prepare(QUERY)?;
+        let mut stmt = self.conn.prepare_cached(QUERY)?;
         let res: u64 = stmt.query_row([], |row| row.get(0))?;
 
         Ok(res)
     }
 
+    /// Get how long a fire burned, from the start of the first scan it was observed in to the end
+    /// of the last one.
+    ///
+    /// Returns `None` if there is no fire with that id in the database.
+    pub fn fire_duration(&self, fire_id: u64) -> SatFireResult<Option<Duration>> {
+        const QUERY: &str = "SELECT last_observed - first_observed FROM fires WHERE fire_id = ?";
+
+        let mut stmt = self.conn.prepare_cached(QUERY)?;
+        let seconds: Option<i64> = stmt.query_row([fire_id], |row| row.get(0)).optional()?;
+
+        Ok(seconds.map(Duration::seconds))
+    }
+
     /// Get the most recent start time
     pub fn last_observed(&self, sat: Satellite) -> Option<DateTime<Utc>> {
         self.conn
-            .query_row(
-                "SELECT MAX(last_observed) FROM fires WHERE satellite = ?",
-                [sat.name()],
-                |row| row.get::<_, i64>(0),
-            )
+            .prepare_cached("SELECT MAX(last_observed) FROM fires WHERE satellite = ?")
+            .and_then(|mut stmt| stmt.query_row([sat.name()], |row| row.get::<_, i64>(0)))
             .map(|time_stamp| {
                 DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(time_stamp, 0), Utc)
             })
@@ -397,7 +942,7 @@ impl FiresDatabase {
         info!(target: sat.name(), "Latest fire observation => {}", latest);
 
         const QUERY: &str = include_str!("database/query_most_recent_fires.sql");
-        let mut stmt = self.conn.prepare(QUERY)?;
+        let mut stmt = self.conn.prepare_cached(QUERY)?;
 
         let mut fires = FireList::new();
 
@@ -425,10 +970,10 @@ impl FiresDatabase {
                 let area = match row.get_ref(6)? {
                     rusqlite::types::ValueRef::Blob(bytes) => {
                         let mut cursor = std::io::Cursor::new(bytes);
-                        Ok(PixelList::binary_deserialize(&mut cursor))
+                        PixelList::binary_deserialize(&mut cursor)?
                     }
-                    _ => Err("Invalid type in pixels column"),
-                }?;
+                    _ => return Err("Invalid type in pixels column".into()),
+                };
 
                 Ok(Fire::new(
                     first_observed,
@@ -464,12 +1009,12 @@ impl FiresDatabase {
     }
 
     /// Add fires and associations to clusters to the database.
-    pub fn prepare_to_add_fires(&self) -> SatFireResult<FiresDatabaseAddFire> {
+    pub fn prepare_to_add_fires(&self) -> SatFireResult<FiresDatabaseAddFire<'_>> {
         const FIRE_QUERY: &str = include_str!("database/add_fire.sql");
         const ASSOC_QUERY: &str = include_str!("database/add_association.sql");
 
-        let fire_stmt = self.conn.prepare(FIRE_QUERY)?;
-        let assoc_stmt = self.conn.prepare(ASSOC_QUERY)?;
+        let fire_stmt = self.conn.prepare_cached(FIRE_QUERY)?;
+        let assoc_stmt = self.conn.prepare_cached(ASSOC_QUERY)?;
         let associations = HashMap::default();
 
         Ok(FiresDatabaseAddFire {
@@ -534,8 +1079,8 @@ impl FiresDatabase {
 
 pub struct FiresDatabaseAddFire<'a> {
     conn: &'a rusqlite::Connection,
-    fire_stmt: rusqlite::Statement<'a>,
-    assoc_stmt: rusqlite::Statement<'a>,
+    fire_stmt: rusqlite::CachedStatement<'a>,
+    assoc_stmt: rusqlite::CachedStatement<'a>,
     associations: HashMap<u64, Vec<u64>>,
 }
 
@@ -617,10 +1162,10 @@ impl<'a> FiresDatabaseQueryFires<'a> {
             let area = match row.get_ref(7)? {
                 rusqlite::types::ValueRef::Blob(bytes) => {
                     let mut cursor = std::io::Cursor::new(bytes);
-                    Ok(PixelList::binary_deserialize(&mut cursor))
+                    PixelList::binary_deserialize(&mut cursor)?
                 }
-                _ => Err("Invalid type in pixels column"),
-            }?;
+                _ => return Err("Invalid type in pixels column".into()),
+            };
 
             Ok(Fire::new(
                 first_observed,
@@ -657,13 +1202,41 @@ impl JointFiresClusterDatabases {
         Ok(JointFiresClusterDatabases { conn })
     }
 
-    pub fn single_fire_query(&self) -> SatFireResult<JointQuerySingleFire> {
+    pub fn single_fire_query(&self) -> SatFireResult<JointQuerySingleFire<'_>> {
         let stmt = self.conn.prepare_cached(include_str!(
             "database/single_fire_clusters_time_series.sql"
         ))?;
 
         Ok(JointQuerySingleFire { stmt })
     }
+
+    /// Get the area of a fire at each time it was observed.
+    ///
+    /// The area is the total [geographic area](crate::Pixel::geographic_area_m2) of the pixels in
+    /// all the clusters associated with the fire, including any fires that merged into it, with
+    /// the same scan start time. The result is sorted by time.
+    pub fn fire_history(&self, fire_id: u64) -> SatFireResult<Vec<(DateTime<Utc>, f64)>> {
+        let mut query = self.single_fire_query()?;
+        let mut history: Vec<(DateTime<Utc>, f64)> = vec![];
+
+        for row in query.run(fire_id)? {
+            let row = row?;
+            let area: f64 = row
+                .pixels
+                .pixels()
+                .iter()
+                .map(|pixel| pixel.geographic_area_m2())
+                .sum();
+
+            // The rows are sorted by start time.
+            match history.last_mut() {
+                Some((time, total)) if *time == row.start => *total += area,
+                _ => history.push((row.start, area)),
+            }
+        }
+
+        Ok(history)
+    }
 }
 
 pub struct JointQuerySingleFire<'a> {
@@ -715,10 +1288,10 @@ fn query_row_to_cluster_row(row: &rusqlite::Row) -> SatFireResult<ClusterDatabas
     let pixels = match row.get_ref(11)? {
         rusqlite::types::ValueRef::Blob(bytes) => {
             let mut cursor = std::io::Cursor::new(bytes);
-            Ok(PixelList::binary_deserialize(&mut cursor))
+            PixelList::binary_deserialize(&mut cursor)?
         }
-        _ => Err("Invalid type in pixels column"),
-    }?;
+        _ => return Err("Invalid type in pixels column".into()),
+    };
 
     Ok(ClusterDatabaseClusterRow {
         rowid,
@@ -734,3 +1307,739 @@ fn query_row_to_cluster_row(row: &rusqlite::Row) -> SatFireResult<ClusterDatabas
         pixels,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cluster::Cluster,
+        pixel::Pixel,
+        satellite::{DataQualityFlagCode, MaskCode},
+        SatFireError,
+    };
+    use chrono::TimeZone;
+
+    struct TempDatabase(std::path::PathBuf);
+
+    impl TempDatabase {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "satfire_{}_{}.sqlite",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            TempDatabase(path)
+        }
+    }
+
+    impl Drop for TempDatabase {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+            // The write-ahead log and shared memory files, if a connection didn't clean them up.
+            let _ = std::fs::remove_file(self.0.with_extension("sqlite-wal"));
+            let _ = std::fs::remove_file(self.0.with_extension("sqlite-shm"));
+        }
+    }
+
+    fn cluster_at(lat: f64, lon: f64) -> Cluster {
+        let mut pixels = PixelList::new();
+        pixels.push(Pixel {
+            ul: Coord {
+                lat: lat + 0.01,
+                lon,
+            },
+            ll: Coord { lat, lon },
+            lr: Coord {
+                lat,
+                lon: lon + 0.01,
+            },
+            ur: Coord {
+                lat: lat + 0.01,
+                lon: lon + 0.01,
+            },
+            power: 10.0,
+            area: 100.0,
+            temperature: 500.0,
+            scan_angle: 3.0,
+            mask_flag: MaskCode(10),
+            data_quality_flag: DataQualityFlagCode(0),
+        });
+
+        Cluster::new(10.0, 100.0, 500.0, 3.0, pixels)
+    }
+
+    fn cluster_list(clusters: Vec<Cluster>) -> ClusterList {
+        let start = Utc.ymd(2022, 7, 4).and_hms(18, 0, 0);
+        let end = Utc.ymd(2022, 7, 4).and_hms(18, 9, 0);
+
+        ClusterList::new(Satellite::G17, Sector::FULL, start, end, clusters)
+    }
+
+    /// A bounding box that covers the whole globe.
+    fn everywhere() -> BoundingBox {
+        BoundingBox {
+            ll: Coord {
+                lat: -90.0,
+                lon: -180.0,
+            },
+            ur: Coord {
+                lat: 90.0,
+                lon: 180.0,
+            },
+        }
+    }
+
+    fn num_clusters(db: &ClusterDatabase) -> i64 {
+        db.conn
+            .query_row("SELECT COUNT(*) FROM clusters", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn is_present(db: &ClusterDatabase) -> bool {
+        let clist = cluster_list(vec![]);
+        db.prepare_to_query_clusters_present()
+            .unwrap()
+            .present(
+                clist.satellite(),
+                clist.sector(),
+                clist.scan_start(),
+                clist.scan_end(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_resume_after_interrupted_add() {
+        let tmp = TempDatabase::new("resume");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+
+        // Simulate a crash part way through adding an image, before the final commit.
+        {
+            let db = ClusterDatabase::connect(&tmp.0).unwrap();
+            let mut add = db.prepare_to_add_clusters().unwrap();
+            add.conn.execute("BEGIN TRANSACTION", []).unwrap();
+            add.insert_clusters(cluster_list(vec![cluster_at(45.0, -120.0)]))
+                .unwrap();
+        }
+
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+        assert_eq!(num_clusters(&db), 0);
+        assert!(!is_present(&db));
+
+        // Re-run, this time to completion.
+        let clusters = vec![cluster_at(45.0, -120.0), cluster_at(46.0, -121.0)];
+        db.prepare_to_add_clusters()
+            .unwrap()
+            .add(cluster_list(clusters))
+            .unwrap();
+
+        assert_eq!(num_clusters(&db), 2);
+        assert!(is_present(&db));
+    }
+
+    #[test]
+    fn test_failed_add_is_rolled_back() {
+        let tmp = TempDatabase::new("rollback");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+        let mut add = db.prepare_to_add_clusters().unwrap();
+
+        // A cluster with no pixels has no centroid, which violates the NOT NULL constraint on lat
+        // and lon after the first cluster was already inserted.
+        let bad = Cluster::new(10.0, 100.0, 500.0, 3.0, PixelList::new());
+        assert!(add
+            .add(cluster_list(vec![cluster_at(45.0, -120.0), bad]))
+            .is_err());
+        assert_eq!(num_clusters(&db), 0);
+        assert!(!is_present(&db));
+
+        // The connection isn't stuck in the failed transaction.
+        add.add(cluster_list(vec![cluster_at(45.0, -120.0)]))
+            .unwrap();
+        assert_eq!(num_clusters(&db), 1);
+        assert!(is_present(&db));
+    }
+
+    fn query_ids(db: &ClusterDatabase, area: BoundingBox) -> Vec<u64> {
+        let start = Utc.ymd(2022, 7, 4).and_hms(0, 0, 0);
+        let end = Utc.ymd(2022, 7, 5).and_hms(0, 0, 0);
+
+        let mut query = db
+            .query_clusters(None, None, start, end, area, None)
+            .unwrap();
+        let mut ids: Vec<u64> = query
+            .rows()
+            .unwrap()
+            .map(|row| row.unwrap().rowid)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn test_query_clusters_spatial_index() {
+        let tmp = TempDatabase::new("rtree");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        let clusters = vec![cluster_at(45.0, -120.0), cluster_at(30.0, -90.0)];
+        db.prepare_to_add_clusters()
+            .unwrap()
+            .add(cluster_list(clusters))
+            .unwrap();
+
+        let west = BoundingBox {
+            ll: Coord {
+                lat: 40.0,
+                lon: -125.0,
+            },
+            ur: Coord {
+                lat: 50.0,
+                lon: -115.0,
+            },
+        };
+        // Overlaps the bounding box of the western cluster, but not its centroid.
+        let edge = BoundingBox {
+            ll: Coord {
+                lat: 45.0,
+                lon: -120.0,
+            },
+            ur: Coord {
+                lat: 45.001,
+                lon: -119.0,
+            },
+        };
+
+        assert_eq!(query_ids(&db, west), vec![1]);
+        assert_eq!(query_ids(&db, everywhere()), vec![1, 2]);
+        assert!(query_ids(&db, edge).is_empty());
+
+        // Simulate a database created before the spatial index existed, initialize migrates it.
+        db.conn.execute("DELETE FROM clusters_rtree", []).unwrap();
+        assert!(query_ids(&db, everywhere()).is_empty());
+
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        assert_eq!(query_ids(&db, west), vec![1]);
+        assert_eq!(query_ids(&db, everywhere()), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_schema_migrations() {
+        let tmp = TempDatabase::new("migrate");
+
+        // A database from before there was a schema version or a spatial index.
+        {
+            let conn = Connection::open(&tmp.0).unwrap();
+            conn.execute_batch(CLUSTER_DB_MIGRATIONS[0].sql).unwrap();
+            let pixels = cluster_at(45.0, -120.0).pixels().binary_serialize();
+            conn.execute(
+                "INSERT INTO clusters (satellite, sector, start_time, end_time, lat, lon, power,
+                   max_temperature, area, max_scan_angle, pixels)
+                 VALUES ('G17', 'FDCF', 1656957600, 1656958140, 45.005, -119.995, 10.0, 500.0,
+                   100.0, 3.0, ?)",
+                [pixels],
+            )
+            .unwrap();
+        }
+
+        // Connecting is enough to migrate the database, including filling the spatial index.
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+        assert_eq!(
+            schema_version(&db.conn).unwrap(),
+            CLUSTER_DB_MIGRATIONS.len()
+        );
+        let num_indexed: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM clusters_rtree", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(num_indexed, 1);
+
+        assert_eq!(query_ids(&db, everywhere()), vec![1]);
+
+        // Connecting again doesn't change anything.
+        drop(db);
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+        assert_eq!(
+            schema_version(&db.conn).unwrap(),
+            CLUSTER_DB_MIGRATIONS.len()
+        );
+
+        // A database from a newer version of the program is refused.
+        db.conn
+            .execute(
+                "UPDATE meta SET value = 99 WHERE key = 'schema_version'",
+                [],
+            )
+            .unwrap();
+        drop(db);
+        assert!(ClusterDatabase::connect(&tmp.0).is_err());
+
+        // A failed step is rolled back along with its version, and its error is reported.
+        let tmp = TempDatabase::new("migrate_fail");
+        let conn = Connection::open(&tmp.0).unwrap();
+        let bad_migrations = [
+            Migration {
+                sql: "CREATE TABLE first (x INTEGER)",
+                backfill: None,
+            },
+            Migration {
+                sql: "CREATE TABLE second (x INTEGER)",
+                backfill: Some(|_| Err(SatFireError::Other("backfill failed".to_owned()))),
+            },
+        ];
+        let err = migrate(&conn, &bad_migrations).unwrap_err();
+        assert!(matches!(err, SatFireError::Other(ref msg) if msg == "backfill failed"));
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+        let num_tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('first', 'second')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(num_tables, 0);
+
+        let tmp = TempDatabase::new("migrate_fires");
+        FiresDatabase::initialize(&tmp.0).unwrap();
+        let db = FiresDatabase::connect(&tmp.0).unwrap();
+        assert_eq!(schema_version(&db.conn).unwrap(), FIRE_DB_MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_connect_to_database_without_spatial_index() {
+        let tmp = TempDatabase::new("no_rtree");
+
+        // A database from before there was a spatial index, with clusters in it.
+        {
+            let conn = Connection::open(&tmp.0).unwrap();
+            conn.execute_batch(CLUSTER_DB_MIGRATIONS[0].sql).unwrap();
+            for (lat, lon) in [(45.0, -120.0), (30.0, -100.0)] {
+                let pixels = cluster_at(lat, lon).pixels().binary_serialize();
+                conn.execute(
+                    "INSERT INTO clusters (satellite, sector, start_time, end_time, lat, lon,
+                       power, max_temperature, area, max_scan_angle, pixels)
+                     VALUES ('G17', 'FDCF', 1656957600, 1656958140, ?, ?, 10.0, 500.0, 100.0,
+                       3.0, ?)",
+                    rusqlite::params![lat + 0.005, lon + 0.005, pixels],
+                )
+                .unwrap();
+            }
+        }
+
+        // Only connect, the programs that read the database never initialize it.
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        assert_eq!(query_ids(&db, everywhere()), vec![1, 2]);
+
+        let west = BoundingBox {
+            ll: Coord {
+                lat: 40.0,
+                lon: -125.0,
+            },
+            ur: Coord {
+                lat: 50.0,
+                lon: -110.0,
+            },
+        };
+        assert_eq!(query_ids(&db, west), vec![1]);
+    }
+
+    #[test]
+    fn test_query_clusters_min_power() {
+        let tmp = TempDatabase::new("min_power");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        let small = cluster_at(45.0, -120.0);
+        let big = cluster_at(46.0, -121.0);
+        let big = Cluster::new(150.0, 100.0, 500.0, 3.0, big.pixels().clone());
+        db.prepare_to_add_clusters()
+            .unwrap()
+            .add(cluster_list(vec![small, big]))
+            .unwrap();
+
+        let start = Utc.ymd(2022, 7, 4).and_hms(0, 0, 0);
+        let end = Utc.ymd(2022, 7, 5).and_hms(0, 0, 0);
+
+        let powers = |min_power: Option<f64>| -> Vec<f64> {
+            let mut query = db
+                .query_clusters(None, None, start, end, everywhere(), min_power)
+                .unwrap();
+            query
+                .rows()
+                .unwrap()
+                .map(|row| row.unwrap().power)
+                .collect()
+        };
+
+        assert_eq!(powers(None).len(), 2);
+        assert_eq!(powers(Some(100.0)), vec![150.0]);
+        assert_eq!(powers(Some(150.0)), vec![150.0]);
+        assert!(powers(Some(200.0)).is_empty());
+
+        // Values that don't format as SQL numbers still make a valid query.
+        assert!(powers(Some(f64::INFINITY)).is_empty());
+        assert_eq!(powers(Some(f64::NEG_INFINITY)).len(), 2);
+        assert!(powers(Some(f64::NAN)).is_empty());
+    }
+
+    #[test]
+    fn test_coverage_summary() {
+        let tmp = TempDatabase::new("coverage");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        assert!(db.coverage_summary().unwrap().is_empty());
+
+        let start = Utc.ymd(2022, 7, 5).and_hms(18, 0, 0);
+        let end = Utc.ymd(2022, 7, 5).and_hms(18, 9, 0);
+        let mut add = db.prepare_to_add_clusters().unwrap();
+        add.add(cluster_list(vec![
+            cluster_at(45.0, -120.0),
+            cluster_at(46.0, -121.0),
+        ]))
+        .unwrap();
+        add.add(ClusterList::new(
+            Satellite::G17,
+            Sector::FULL,
+            start,
+            end,
+            vec![cluster_at(45.0, -120.0)],
+        ))
+        .unwrap();
+        add.add(ClusterList::new(
+            Satellite::G16,
+            Sector::CONUS,
+            start,
+            end,
+            vec![cluster_at(35.0, -90.0)],
+        ))
+        .unwrap();
+        drop(add);
+
+        let coverage = db.coverage_summary().unwrap();
+        assert_eq!(
+            coverage,
+            vec![
+                CoverageEntry {
+                    satellite: Satellite::G16,
+                    sector: Sector::CONUS,
+                    earliest_scan: start,
+                    latest_scan: start,
+                    cluster_count: 1,
+                },
+                CoverageEntry {
+                    satellite: Satellite::G17,
+                    sector: Sector::FULL,
+                    earliest_scan: Utc.ymd(2022, 7, 4).and_hms(18, 0, 0),
+                    latest_scan: start,
+                    cluster_count: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_before() {
+        let tmp = TempDatabase::new("delete_before");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        let count = |table: &str| -> i64 {
+            db.conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+
+        // An old image with clusters, an old one without, and a new image with clusters.
+        let new_start = Utc.ymd(2022, 7, 5).and_hms(18, 0, 0);
+        let new_end = Utc.ymd(2022, 7, 5).and_hms(18, 9, 0);
+        let mut add = db.prepare_to_add_clusters().unwrap();
+        add.add(cluster_list(vec![
+            cluster_at(45.0, -120.0),
+            cluster_at(46.0, -121.0),
+        ]))
+        .unwrap();
+        add.add(ClusterList::new(
+            Satellite::G17,
+            Sector::CONUS,
+            Utc.ymd(2022, 7, 4).and_hms(18, 0, 0),
+            Utc.ymd(2022, 7, 4).and_hms(18, 5, 0),
+            vec![],
+        ))
+        .unwrap();
+        add.add(ClusterList::new(
+            Satellite::G17,
+            Sector::FULL,
+            new_start,
+            new_end,
+            vec![cluster_at(47.0, -122.0)],
+        ))
+        .unwrap();
+        drop(add);
+
+        assert_eq!(db.delete_before(new_start).unwrap(), 2);
+        assert_eq!(count("clusters"), 1);
+        assert_eq!(count("clusters_rtree"), 1);
+        assert_eq!(count("no_clusters"), 0);
+
+        let mut query = db
+            .query_clusters(None, None, new_start, new_end, everywhere(), None)
+            .unwrap();
+        let rows: Vec<_> = query.rows().unwrap().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 1);
+        assert!((rows[0].centroid.lat - 47.005).abs() < 1.0e-6);
+        drop(query);
+
+        // Nothing left to delete.
+        assert_eq!(db.delete_before(new_start).unwrap(), 0);
+        assert_eq!(count("clusters"), 1);
+    }
+
+    #[test]
+    fn test_fire_history() {
+        let clusters_tmp = TempDatabase::new("history_clusters");
+        let fires_tmp = TempDatabase::new("history_fires");
+        ClusterDatabase::initialize(&clusters_tmp.0).unwrap();
+        FiresDatabase::initialize(&fires_tmp.0).unwrap();
+
+        // One cluster at the first time, then it grows to two clusters two hours later.
+        let first = Utc.ymd(2022, 7, 4).and_hms(18, 0, 0);
+        let second = Utc.ymd(2022, 7, 4).and_hms(20, 0, 0);
+        let cdb = ClusterDatabase::connect(&clusters_tmp.0).unwrap();
+        let mut add = cdb.prepare_to_add_clusters().unwrap();
+        add.add(cluster_list(vec![cluster_at(45.0, -120.0)]))
+            .unwrap();
+        add.add(ClusterList::new(
+            Satellite::G17,
+            Sector::FULL,
+            second,
+            second + Duration::minutes(9),
+            vec![cluster_at(45.0, -120.0), cluster_at(45.0, -119.99)],
+        ))
+        .unwrap();
+        drop(add);
+
+        let mut query = cdb
+            .query_clusters(
+                None,
+                None,
+                first,
+                second + Duration::hours(1),
+                everywhere(),
+                None,
+            )
+            .unwrap();
+        let rows: Vec<_> = query.rows().unwrap().map(|row| row.unwrap()).collect();
+        drop(query);
+        assert_eq!(rows.len(), 3);
+
+        let pixel_area = rows[0].pixels.pixels()[0].geographic_area_m2();
+
+        let mut fire = Fire::create_from_cluster(1, rows[0].clone());
+        fire.update(&rows[1]);
+        fire.update(&rows[2]);
+        let mut fires = FireList::new();
+        fires.add_fire(fire);
+
+        let fdb = FiresDatabase::connect(&fires_tmp.0).unwrap();
+        let mut add = fdb.prepare_to_add_fires().unwrap();
+        for row in &rows {
+            add.add_association(1, row.rowid);
+        }
+        add.add_fires(&fires).unwrap();
+        drop(add);
+        drop(fdb);
+
+        let dbs = JointFiresClusterDatabases::connect(&clusters_tmp.0, &fires_tmp.0).unwrap();
+        let history = dbs.fire_history(1).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].0, first);
+        assert!((history[0].1 - pixel_area).abs() < 1.0);
+        assert_eq!(history[1].0, second);
+        assert!((history[1].1 - 2.0 * pixel_area).abs() < 1.0);
+
+        // A fire that doesn't exist has no history.
+        assert!(dbs.fire_history(2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fire_duration() {
+        let tmp = TempDatabase::new("fire_duration");
+        FiresDatabase::initialize(&tmp.0).unwrap();
+        let db = FiresDatabase::connect(&tmp.0).unwrap();
+
+        let first = Utc.ymd(2022, 7, 4).and_hms(18, 0, 0);
+        let row = ClusterDatabaseClusterRow {
+            rowid: 1,
+            start: first,
+            end: first + Duration::minutes(10),
+            power: 10.0,
+            max_temperature: 500.0,
+            area: 100.0,
+            scan_angle: 3.0,
+            centroid: Coord {
+                lat: 45.005,
+                lon: -119.995,
+            },
+            sector: Sector::FULL,
+            sat: Satellite::G17,
+            pixels: cluster_at(45.0, -120.0).pixels().clone(),
+        };
+
+        let mut fire = Fire::create_from_cluster(1, row.clone());
+        fire.update(&ClusterDatabaseClusterRow {
+            start: first + Duration::hours(3),
+            end: first + Duration::hours(3) + Duration::minutes(10),
+            ..row
+        });
+        let mut fires = FireList::new();
+        fires.add_fire(fire);
+        db.prepare_to_add_fires()
+            .unwrap()
+            .add_fires(&fires)
+            .unwrap();
+
+        assert_eq!(
+            db.fire_duration(1).unwrap(),
+            Some(Duration::hours(3) + Duration::minutes(10))
+        );
+        assert_eq!(db.fire_duration(2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_connection_pragmas() {
+        let tmp = TempDatabase::new("pragmas");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        let pragma = |name: &str| -> String {
+            db.conn
+                .pragma_query_value(None, name, |row| row.get::<_, rusqlite::types::Value>(0))
+                .map(|val| match val {
+                    rusqlite::types::Value::Text(txt) => txt,
+                    rusqlite::types::Value::Integer(i) => i.to_string(),
+                    val => panic!("unexpected pragma value {:?}", val),
+                })
+                .unwrap()
+        };
+
+        assert_eq!(pragma("journal_mode"), "wal");
+        assert_eq!(pragma("synchronous"), "1");
+        assert_eq!(pragma("cache_size"), "-65536");
+        assert_eq!(pragma("auto_vacuum"), "2");
+
+        db.set_durability(Durability::Full).unwrap();
+        assert_eq!(pragma("synchronous"), "2");
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let tmp = TempDatabase::new("csv");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        db.prepare_to_add_clusters()
+            .unwrap()
+            .add(cluster_list(vec![cluster_at(45.0, -120.0)]))
+            .unwrap();
+
+        let start = Utc.ymd(2022, 7, 4).and_hms(0, 0, 0);
+        let end = Utc.ymd(2022, 7, 5).and_hms(0, 0, 0);
+
+        let mut buf = vec![];
+        db.export_csv(None, None, start, end, everywhere(), &mut buf)
+            .unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+
+        let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "satellite,sector,start,end,lat,lon,power_mw,area_m2,max_temperature_k,\
+             max_scan_angle,pixel_count"
+        );
+
+        let fields: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(fields.len(), 11);
+        assert_eq!(
+            &fields[..4],
+            [
+                "G17",
+                "FDCF",
+                "2022-07-04T18:00:00+00:00",
+                "2022-07-04T18:09:00+00:00"
+            ]
+        );
+        assert_eq!(&fields[6..], ["10", "100", "500", "3", "1"]);
+    }
+
+    #[test]
+    fn test_write_ndjson() {
+        let tmp = TempDatabase::new("ndjson");
+        ClusterDatabase::initialize(&tmp.0).unwrap();
+        let db = ClusterDatabase::connect(&tmp.0).unwrap();
+
+        db.prepare_to_add_clusters()
+            .unwrap()
+            .add(cluster_list(vec![
+                cluster_at(45.0, -120.0),
+                cluster_at(30.0, -100.0),
+            ]))
+            .unwrap();
+
+        let start = Utc.ymd(2022, 7, 4).and_hms(0, 0, 0);
+        let end = Utc.ymd(2022, 7, 5).and_hms(0, 0, 0);
+        let area = BoundingBox {
+            ll: Coord {
+                lat: 40.0,
+                lon: -125.0,
+            },
+            ur: Coord {
+                lat: 50.0,
+                lon: -110.0,
+            },
+        };
+
+        let mut buf = vec![];
+        db.write_ndjson(None, None, start, end, area, &mut buf)
+            .unwrap();
+        let text = String::from_utf8(buf).unwrap();
+
+        // Only the cluster inside the bounding box.
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1);
+        assert!(text.ends_with('\n'));
+
+        let obj: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        let keys: Vec<&String> = obj.as_object().unwrap().keys().collect();
+        let mut expected_keys = EXPORT_FIELDS.to_vec();
+        expected_keys.sort_unstable();
+        assert_eq!(keys, expected_keys);
+
+        assert_eq!(obj["satellite"], "G17");
+        assert_eq!(obj["sector"], "FDCF");
+        assert_eq!(obj["start"], "2022-07-04T18:00:00+00:00");
+        assert_eq!(obj["end"], "2022-07-04T18:09:00+00:00");
+        assert!((obj["lat"].as_f64().unwrap() - 45.005).abs() < 1.0e-6);
+        assert_eq!(obj["power_mw"], 10.0);
+        assert_eq!(obj["area_m2"], 100.0);
+        assert_eq!(obj["max_temperature_k"], 500.0);
+        assert_eq!(obj["max_scan_angle"], 3.0);
+        assert_eq!(obj["pixel_count"], 1);
+    }
+
+    #[test]
+    fn test_write_csv_record_quoting() {
+        let mut buf = vec![];
+        write_csv_record(&mut buf, &["plain", "a,b", "say \"hi\"", "two\nlines", ""]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\r\n"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+-- Spatial index on the bounding box of each cluster.
+CREATE VIRTUAL TABLE IF NOT EXISTS clusters_rtree USING rtree(
+  cluster_id,
+  min_lat, max_lat,
+  min_lon, max_lon);
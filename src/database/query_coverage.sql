@@ -0,0 +1,4 @@
+SELECT satellite, sector, MIN(start_time), MAX(start_time), COUNT(*)
+FROM clusters
+GROUP BY satellite, sector
+ORDER BY satellite, sector
@@ -1,4 +1,5 @@
 use crate::{
+    cluster::ClusterList,
     database::ClusterDatabaseClusterRow,
     geo::{BoundingBox, Coord, Geo, Hilbert2DRTreeView},
     pixel::PixelList,
@@ -100,6 +101,7 @@ impl Display for Fire {
 
 impl Fire {
     /// Create a new fire from the raw parts.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         first_observed: DateTime<Utc>,
         last_observed: DateTime<Utc>,
@@ -275,6 +277,25 @@ pub enum FireListUpdateResult {
     Match(u64),
 }
 
+/// A change in how the clusters of a scan line up with the fires from earlier scans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FireEvent {
+    /// A cluster overlaps more than one fire, so those fires have grown together.
+    Merge {
+        /// The index of the cluster in the [ClusterList].
+        cluster: usize,
+        /// The ids of the fires it overlaps, in ascending order.
+        fire_ids: Vec<u64>,
+    },
+    /// A fire overlaps more than one cluster, so it has broken into pieces.
+    Split {
+        /// The id of the fire.
+        fire_id: u64,
+        /// The indexes of the clusters in the [ClusterList] that overlap it, in ascending order.
+        clusters: Vec<usize>,
+    },
+}
+
 impl Default for FireList {
     fn default() -> Self {
         Self::new()
@@ -321,17 +342,64 @@ impl FireList {
         let cluster_bbox = cluster_pixels.bounding_box();
 
         for fire in self.0.iter_mut() {
-            if cluster_bbox.overlap(&fire.bounding_box(), OVERLAP_FUDGE_FACTOR) {
-                if cluster_pixels.adjacent_to_or_overlaps(&fire.area, OVERLAP_FUDGE_FACTOR) {
-                    fire.update(&row);
-                    return FireListUpdateResult::Match(fire.id);
-                }
+            if cluster_bbox.overlap(&fire.bounding_box(), OVERLAP_FUDGE_FACTOR)
+                && cluster_pixels.adjacent_to_or_overlaps(&fire.area, OVERLAP_FUDGE_FACTOR)
+            {
+                fire.update(&row);
+                return FireListUpdateResult::Match(fire.id);
             }
         }
 
         FireListUpdateResult::NoMatch(row)
     }
 
+    /// Find the fires that merged or split in a new scan.
+    ///
+    /// A cluster and a fire overlap if they are adjacent or overlapping, the same test used to
+    /// match clusters to fires in [FireList::update]. Neither this list nor the clusters are
+    /// changed, so this should be called before the clusters are used to update the fires. All
+    /// the merges come first, ordered by cluster, followed by the splits in the order of the
+    /// fires in this list.
+    pub fn merges_and_splits(&self, clusters: &ClusterList) -> Vec<FireEvent> {
+        // For each fire, the indexes of the clusters that overlap it.
+        let mut fire_overlaps: Vec<Vec<usize>> = vec![vec![]; self.0.len()];
+        let mut events = vec![];
+
+        for (cluster_idx, cluster) in clusters.clusters().iter().enumerate() {
+            let cluster_pixels = cluster.pixels();
+            let cluster_bbox = cluster.bounding_box();
+
+            let mut fire_ids = vec![];
+            for (fire, overlaps) in self.0.iter().zip(fire_overlaps.iter_mut()) {
+                if cluster_bbox.overlap(&fire.bounding_box(), OVERLAP_FUDGE_FACTOR)
+                    && cluster_pixels.adjacent_to_or_overlaps(&fire.area, OVERLAP_FUDGE_FACTOR)
+                {
+                    fire_ids.push(fire.id);
+                    overlaps.push(cluster_idx);
+                }
+            }
+
+            if fire_ids.len() > 1 {
+                fire_ids.sort_unstable();
+                events.push(FireEvent::Merge {
+                    cluster: cluster_idx,
+                    fire_ids,
+                });
+            }
+        }
+
+        for (fire, clusters) in self.0.iter().zip(fire_overlaps) {
+            if clusters.len() > 1 {
+                events.push(FireEvent::Split {
+                    fire_id: fire.id,
+                    clusters,
+                });
+            }
+        }
+
+        events
+    }
+
     /// Extend a fire list using another fire list, the `src` list is left empty.
     ///
     /// Returns the number of items added to this list.
@@ -514,7 +582,7 @@ impl FireList {
             kmz.create_point(centroid.lat, centroid.lon, 0.0)?;
             kmz.finish_placemark()?;
 
-            fire.pixels().kml_write(&mut kmz);
+            fire.pixels().kml_write(&mut kmz, None);
             kmz.finish_folder()?;
         }
 
@@ -605,3 +673,99 @@ fn wildfire_is_stale(fire: &Fire, current_time: DateTime<Utc>) -> bool {
     let wildfire_duration = fire.duration();
     wildfire_duration < duration_since_last_observed
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cluster::Cluster,
+        pixel::Pixel,
+        satellite::{DataQualityFlagCode, MaskCode, Sector},
+    };
+
+    /// A square pixel with its lower left corner at `(lat, lon)` and sides of `size` degrees.
+    fn square(lat: f64, lon: f64, size: f64) -> PixelList {
+        let mut pixels = PixelList::new();
+        pixels.push(Pixel {
+            ul: Coord {
+                lat: lat + size,
+                lon,
+            },
+            ll: Coord { lat, lon },
+            lr: Coord {
+                lat,
+                lon: lon + size,
+            },
+            ur: Coord {
+                lat: lat + size,
+                lon: lon + size,
+            },
+            power: 10.0,
+            area: 100.0,
+            temperature: 500.0,
+            scan_angle: 3.0,
+            mask_flag: MaskCode(10),
+            data_quality_flag: DataQualityFlagCode(0),
+        });
+        pixels
+    }
+
+    fn fire(id: u64, pixels: PixelList) -> Fire {
+        let start = DateTime::<Utc>::from_utc(
+            chrono::NaiveDate::from_ymd(2022, 7, 4).and_hms(18, 0, 0),
+            Utc,
+        );
+        Fire::new(start, start, 10.0, 500.0, id, pixels, Satellite::G17, 0)
+    }
+
+    fn clusters(pixels: Vec<PixelList>) -> ClusterList {
+        let start = DateTime::<Utc>::from_utc(
+            chrono::NaiveDate::from_ymd(2022, 7, 4).and_hms(19, 0, 0),
+            Utc,
+        );
+        let clusters = pixels
+            .into_iter()
+            .map(|pixels| Cluster::new(10.0, 100.0, 500.0, 3.0, pixels))
+            .collect();
+        ClusterList::new(Satellite::G17, Sector::FULL, start, start, clusters)
+    }
+
+    #[test]
+    fn test_merges_and_splits() {
+        let fires = FireList::from(vec![
+            fire(1, square(45.0, -120.0, 0.1)),
+            fire(2, square(45.0, -119.8, 0.1)),
+            fire(3, square(40.0, -110.0, 0.3)),
+            fire(4, square(30.0, -100.0, 0.1)),
+        ]);
+
+        let clist = clusters(vec![
+            // Covers the gap between fires 1 and 2.
+            square(45.0, -120.0, 0.3),
+            // Two pieces of fire 3.
+            square(40.0, -110.0, 0.1),
+            square(40.2, -109.8, 0.1),
+            // Just fire 4.
+            square(30.0, -100.0, 0.1),
+            // Nowhere near a fire.
+            square(20.0, -90.0, 0.1),
+        ]);
+
+        assert_eq!(
+            fires.merges_and_splits(&clist),
+            vec![
+                FireEvent::Merge {
+                    cluster: 0,
+                    fire_ids: vec![1, 2],
+                },
+                FireEvent::Split {
+                    fire_id: 3,
+                    clusters: vec![1, 2],
+                },
+            ]
+        );
+
+        assert!(FireList::new().merges_and_splits(&clist).is_empty());
+        assert!(fires.merges_and_splits(&clusters(vec![])).is_empty());
+    }
+}
@@ -2,9 +2,11 @@ use crate::{
     geo::Coord,
     pixel::Pixel,
     satellite::{DataQualityFlagCode, MaskCode},
-    SatFireResult,
+    SatFireError, SatFireResult,
 };
+use chrono::{DateTime, Utc};
 use libc::{c_char, c_double, c_int, c_short, c_void, size_t};
+use log::debug;
 use once_cell::sync::OnceCell;
 use std::{
     ffi::{CStr, CString},
@@ -33,6 +35,38 @@ pub(crate) struct SatFireImage {
     nc_file_id: c_int,
     /// Orignial file name the dataset was loaded from.
     fname: String,
+    /// The scan start time from the time_coverage_start global attribute, if present.
+    start: Option<DateTime<Utc>>,
+    /// The scan end time from the time_coverage_end global attribute, if present.
+    end: Option<DateTime<Utc>>,
+}
+
+/// Scratch space for reading the variables from an image.
+///
+/// Every image from a sector is the same size, so a thread that processes many files can keep one
+/// of these and avoid allocating (and freeing) several image sized buffers for each file. See
+/// [ClusterListOptions::buffers](crate::ClusterListOptions::buffers).
+#[derive(Debug, Default)]
+pub struct ImageBuffers {
+    powers: Vec<f64>,
+    areas: Vec<f64>,
+    temperatures: Vec<f64>,
+    masks: Vec<i16>,
+    dqfs: Vec<i16>,
+}
+
+impl ImageBuffers {
+    /// Create new, empty buffers. Nothing is allocated until they are used.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Empty a buffer and make sure it has room for `len` values. This only allocates if the buffer
+/// has never held that many values.
+fn clear_with_capacity<T>(buf: &mut Vec<T>, len: usize) {
+    buf.clear();
+    buf.reserve(len);
 }
 
 macro_rules! check_error {
@@ -92,11 +126,11 @@ impl SatFireImage {
                 &mut file_id as *mut c_int,
             );
             if status != NC_NOERR {
-                return Err(format!(
-                    "Error opening netcdf: {}",
+                return Err(SatFireError::NetCdf(format!(
+                    "error opening {}: {}",
+                    fname,
                     std::str::from_utf8_unchecked(CStr::from_ptr(nc_strerror(status)).to_bytes())
-                )
-                .into());
+                )));
             }
         }
 
@@ -149,39 +183,31 @@ impl SatFireImage {
 
         unsafe {
             let mut xdimid: c_int = -1;
-            let mut status = nc_inq_dimid(
-                h,
-                b"x\0".as_ptr() as *const c_char,
-                &mut xdimid as *mut c_int,
-            );
+            let mut status = nc_inq_dimid(h, c"x".as_ptr(), &mut xdimid as *mut c_int);
             check_error!(status)?;
             status = nc_inq_dimlen(h, xdimid, &mut xlen as *mut size_t);
             check_error!(status)?;
 
             let mut ydimid: c_int = -1;
-            status = nc_inq_dimid(
-                h,
-                b"y\0".as_ptr() as *const c_char,
-                &mut ydimid as *mut c_int,
-            );
+            status = nc_inq_dimid(h, c"y".as_ptr(), &mut ydimid as *mut c_int);
             check_error!(status)?;
             status = nc_inq_dimlen(h, ydimid, &mut ylen as *mut size_t);
             check_error!(status)?;
 
             let mut x: c_int = -1;
             let mut y: c_int = -1;
-            status = nc_inq_varid(h, b"x\0".as_ptr() as *const c_char, &mut x as *mut c_int);
+            status = nc_inq_varid(h, c"x".as_ptr(), &mut x as *mut c_int);
             check_error!(status)?;
-            status = nc_inq_varid(h, b"y\0".as_ptr() as *const c_char, &mut y as *mut c_int);
+            status = nc_inq_varid(h, c"y".as_ptr(), &mut y as *mut c_int);
             check_error!(status)?;
 
-            let scale_factor = b"scale_factor\0".as_ptr() as *const c_char;
+            let scale_factor = c"scale_factor".as_ptr();
             status = nc_get_att_double(h, x, scale_factor, &mut xscale as *mut c_double);
             check_error!(status)?;
             status = nc_get_att_double(h, y, scale_factor, &mut yscale as *mut c_double);
             check_error!(status)?;
 
-            let add_offset = b"add_offset\0".as_ptr() as *const c_char;
+            let add_offset = c"add_offset".as_ptr();
             status = nc_get_att_double(h, x, add_offset, &mut xoffset as *mut c_double);
             check_error!(status)?;
             status = nc_get_att_double(h, y, add_offset, &mut yoffset as *mut c_double);
@@ -190,15 +216,15 @@ impl SatFireImage {
             let mut proj_id: c_int = -1;
             status = nc_inq_varid(
                 h,
-                b"goes_imager_projection\0".as_ptr() as *const c_char,
+                c"goes_imager_projection".as_ptr(),
                 &mut proj_id as *mut c_int,
             );
             check_error!(status)?;
 
-            let semi_major_axis = b"semi_major_axis\0".as_ptr() as *const c_char;
-            let semi_minor_axis = b"semi_minor_axis\0".as_ptr() as *const c_char;
-            let perp_point_h = b"perspective_point_height\0".as_ptr() as *const c_char;
-            let lon_origin = b"longitude_of_projection_origin\0".as_ptr() as *const c_char;
+            let semi_major_axis = c"semi_major_axis".as_ptr();
+            let semi_minor_axis = c"semi_minor_axis".as_ptr();
+            let perp_point_h = c"perspective_point_height".as_ptr();
+            let lon_origin = c"longitude_of_projection_origin".as_ptr();
             status = nc_get_att_double(h, proj_id, semi_major_axis, &mut req as *mut c_double);
             check_error!(status)?;
             status = nc_get_att_double(h, proj_id, semi_minor_axis, &mut rpol as *mut c_double);
@@ -209,6 +235,9 @@ impl SatFireImage {
             check_error!(status)?;
         }
 
+        let start = read_global_time_attribute(h, c"time_coverage_start")?;
+        let end = read_global_time_attribute(h, c"time_coverage_end")?;
+
         Ok(SatFireImage {
             xlen,
             ylen,
@@ -225,21 +254,56 @@ impl SatFireImage {
             buffer: in_memory_buffer,
             nc_file_id: handle,
             fname,
+            start,
+            end,
         })
     }
 
-    pub(crate) fn extract_fire_points(&self) -> SatFireResult<Vec<FirePoint>> {
+    /// Get the scan start time.
+    ///
+    /// This comes from the time_coverage_start global attribute in the file, or from the file
+    /// name if the attribute is missing.
+    pub(crate) fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.start
+            .or_else(|| crate::start_time_from_file_name(&self.fname))
+    }
+
+    /// Get the scan end time.
+    ///
+    /// This comes from the time_coverage_end global attribute in the file, or from the file name
+    /// if the attribute is missing.
+    pub(crate) fn end_time(&self) -> Option<DateTime<Utc>> {
+        self.end
+            .or_else(|| crate::end_time_from_file_name(&self.fname))
+    }
+
+    /// Find the fire points in the image. The variables are read into `buffers` so they can be
+    /// reused for the next image.
+    pub(crate) fn extract_fire_points_with_buffers(
+        &self,
+        buffers: &mut ImageBuffers,
+    ) -> SatFireResult<Vec<FirePoint>> {
         let mut points: Vec<FirePoint> = Vec::new();
 
         let lock = get_netcdf_lock()
             .lock()
             .expect("Error locking global mutex for netCDF");
 
-        let powers = self.extract_variable_double(b"Power\0".as_ptr() as *const c_char)?;
-        let areas = self.extract_variable_double(b"Area\0".as_ptr() as *const c_char)?;
-        let temperatures = self.extract_variable_double(b"Temp\0".as_ptr() as *const c_char)?;
-        let masks = self.extract_variable_short(b"Mask\0".as_ptr() as *const c_char)?;
-        let dqfs = self.extract_variable_short(b"DQF\0".as_ptr() as *const c_char)?;
+        let ImageBuffers {
+            powers,
+            areas,
+            temperatures,
+            masks,
+            dqfs,
+        } = buffers;
+
+        self.extract_variable_double(c"Power".as_ptr(), powers)?;
+        self.extract_optional_variable_double(c"Area".as_ptr(), areas)?;
+        self.extract_optional_variable_double(c"Temp".as_ptr(), temperatures)?;
+        // If the mask is missing, mark it as missing. If the DQF is missing there was no quality
+        // control, so treat every pixel with a fire power as a good quality detection (0).
+        self.extract_optional_variable_short(c"Mask".as_ptr(), -99, masks)?;
+        self.extract_optional_variable_short(c"DQF".as_ptr(), 0, dqfs)?;
 
         drop(lock);
 
@@ -261,8 +325,9 @@ impl SatFireImage {
                     dqf = *dqfs.get_unchecked(index);
                 }
 
-                // 0 for a data quality flag indicates a good quality fire detection
-                if dqf == 0 {
+                // 0 for a data quality flag indicates a good quality fire detection. Pixels without
+                // a fire have a missing power, so they are skipped when the DQF is missing too.
+                if dqf == 0 && power_mw.is_finite() {
                     let ii = i as f64;
                     let jj = j as f64;
 
@@ -294,8 +359,12 @@ impl SatFireImage {
         Ok(points)
     }
 
-    fn extract_variable_double(&self, vname: *const c_char) -> SatFireResult<Vec<f64>> {
-        let mut vals = Vec::with_capacity(self.xlen * self.ylen);
+    fn extract_variable_double(
+        &self,
+        vname: *const c_char,
+        vals: &mut Vec<f64>,
+    ) -> SatFireResult<()> {
+        clear_with_capacity(vals, self.xlen * self.ylen);
 
         let mut skip_transform;
         let mut scale_factor: f64 = 1.0;
@@ -316,9 +385,9 @@ impl SatFireImage {
 
             vals.set_len(self.ylen * self.xlen);
 
-            let scale_str = b"scale_factor\0".as_ptr() as *const c_char;
-            let offset_str = b"add_offset\0".as_ptr() as *const c_char;
-            let fill_str = b"_FillValue\0".as_ptr() as *const c_char;
+            let scale_str = c"scale_factor".as_ptr();
+            let offset_str = c"add_offset".as_ptr();
+            let fill_str = c"_FillValue".as_ptr();
             status = nc_get_att_double(fid, varid, scale_str, &mut scale_factor as *mut c_double);
             check_error!(status, "attr")?;
             skip_transform = status == NC_ENOTATT;
@@ -345,11 +414,65 @@ impl SatFireImage {
             }
         }
 
-        Ok(vals)
+        Ok(())
+    }
+
+    /// Same as extract_variable_double, except if the variable is not in the file all the values
+    /// are NaN.
+    fn extract_optional_variable_double(
+        &self,
+        vname: *const c_char,
+        vals: &mut Vec<f64>,
+    ) -> SatFireResult<()> {
+        if self.has_variable(vname)? {
+            self.extract_variable_double(vname, vals)
+        } else {
+            clear_with_capacity(vals, self.xlen * self.ylen);
+            vals.resize(self.xlen * self.ylen, f64::NAN);
+            Ok(())
+        }
+    }
+
+    /// Same as extract_variable_short, except if the variable is not in the file all the values
+    /// are set to missing_value.
+    fn extract_optional_variable_short(
+        &self,
+        vname: *const c_char,
+        missing_value: i16,
+        vals: &mut Vec<i16>,
+    ) -> SatFireResult<()> {
+        if self.has_variable(vname)? {
+            self.extract_variable_short(vname, vals)
+        } else {
+            clear_with_capacity(vals, self.xlen * self.ylen);
+            vals.resize(self.xlen * self.ylen, missing_value);
+            Ok(())
+        }
+    }
+
+    /// Check if a variable is in the file, and log it at the debug level if it is missing.
+    fn has_variable(&self, vname: *const c_char) -> SatFireResult<bool> {
+        let status = unsafe {
+            let mut varid: c_int = -1;
+            nc_inq_varid(self.nc_file_id, vname, &mut varid as *mut c_int)
+        };
+
+        if status == NC_ENOTVAR {
+            let vname = unsafe { CStr::from_ptr(vname) }.to_string_lossy();
+            debug!("Variable {} missing from {}", vname, self.fname);
+            return Ok(false);
+        }
+        check_error!(status)?;
+
+        Ok(true)
     }
 
-    fn extract_variable_short(&self, vname: *const c_char) -> SatFireResult<Vec<i16>> {
-        let mut vals = Vec::with_capacity(self.xlen * self.ylen);
+    fn extract_variable_short(
+        &self,
+        vname: *const c_char,
+        vals: &mut Vec<i16>,
+    ) -> SatFireResult<()> {
+        clear_with_capacity(vals, self.xlen * self.ylen);
 
         unsafe {
             let mut varid: c_int = -1;
@@ -371,7 +494,7 @@ impl SatFireImage {
             vals.set_len(self.ylen * self.xlen);
         }
 
-        Ok(vals)
+        Ok(())
     }
 }
 
@@ -477,25 +600,63 @@ fn get_netcdf_lock() -> &'static Mutex<()> {
 }
 
 const NC_NOWRITE: c_int = 0x0000;
+const NC_GLOBAL: c_int = -1;
 const NC_NOERR: c_int = 0;
 const NC_ENOTATT: c_int = -43;
+const NC_ENOTVAR: c_int = -49;
 
 fn check_netcdf_error(status_code: c_int, file: &'static str, line: u32) -> SatFireResult<()> {
     unsafe {
         if status_code != NC_NOERR {
-            Err(format!(
-                "{}[{}]netCDF error: {}",
+            Err(SatFireError::NetCdf(format!(
+                "{}[{}]: {}",
                 file,
                 line,
                 std::str::from_utf8_unchecked(CStr::from_ptr(nc_strerror(status_code)).to_bytes())
-            )
-            .into())
+            )))
         } else {
             Ok(())
         }
     }
 }
 
+/// Read a global attribute holding a time, e.g. "2021-08-01T01:00:31.9Z".
+///
+/// Returns None if the attribute is missing or can't be parsed.
+fn read_global_time_attribute(
+    handle: c_int,
+    name: &'static CStr,
+) -> SatFireResult<Option<DateTime<Utc>>> {
+    let name = name.as_ptr();
+    let mut len: size_t = 0;
+
+    let mut buf: Vec<u8>;
+    unsafe {
+        let status = nc_inq_attlen(handle, NC_GLOBAL, name, &mut len as *mut size_t);
+        check_error!(status, "attr")?;
+        if status == NC_ENOTATT {
+            return Ok(None);
+        }
+
+        buf = vec![0; len];
+        let status = nc_get_att_text(handle, NC_GLOBAL, name, buf.as_mut_ptr() as *mut c_char);
+        check_error!(status, "attr")?;
+        if status == NC_ENOTATT {
+            return Ok(None);
+        }
+    }
+
+    Ok(parse_time_coverage(&String::from_utf8_lossy(&buf)))
+}
+
+/// Parse the time format used in the time_coverage_start and time_coverage_end attributes.
+fn parse_time_coverage(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim_end_matches('\0').trim();
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
 fn check_netcdf_attribute_error(
     status_code: c_int,
     file: &'static str,
@@ -503,13 +664,12 @@ fn check_netcdf_attribute_error(
 ) -> SatFireResult<()> {
     unsafe {
         if status_code != NC_NOERR && status_code != NC_ENOTATT {
-            Err(format!(
-                "{}[{}]netCDF error loading attribute: {}",
+            Err(SatFireError::NetCdf(format!(
+                "{}[{}] loading attribute: {}",
                 file,
                 line,
                 std::str::from_utf8_unchecked(CStr::from_ptr(nc_strerror(status_code)).to_bytes())
-            )
-            .into())
+            )))
         } else {
             Ok(())
         }
@@ -539,6 +699,9 @@ extern "C" {
         name: *const c_char,
         val: *mut c_double,
     ) -> c_int;
+    fn nc_inq_attlen(handle: c_int, varid: c_int, name: *const c_char, len: *mut size_t) -> c_int;
+    fn nc_get_att_text(handle: c_int, varid: c_int, name: *const c_char, val: *mut c_char)
+        -> c_int;
     fn nc_get_vara_short(
         handle: c_int,
         varid: c_int,
@@ -554,3 +717,114 @@ extern "C" {
         vals: *mut c_double,
     ) -> c_int;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse_time_coverage() {
+        let expected = DateTime::<Utc>::from_utc(
+            NaiveDate::from_yo(2021, 213).and_hms_milli(1, 0, 31, 900),
+            Utc,
+        );
+
+        assert_eq!(
+            parse_time_coverage("2021-08-01T01:00:31.9Z"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_time_coverage("2021-08-01T01:00:31.9Z\0"),
+            Some(expected)
+        );
+        assert_eq!(parse_time_coverage("20212130100319"), None);
+    }
+
+    /// Path to a test file. These are small (4 x 3 pixel) CONUS images with three good quality
+    /// fire pixels and one cloud contaminated fire pixel (DQF = 2) at column 0, row 2.
+    fn test_file(fname: &str) -> String {
+        format!("{}/test_data/{}", env!("CARGO_MANIFEST_DIR"), fname)
+    }
+
+    const FILE_WITH_DQF: &str =
+        "OR_ABI-L2-FDCC-M6_G17_s20221851801177_e20221851803550_c20221851804313.nc";
+    const FILE_WITHOUT_DQF: &str =
+        "OR_ABI-L2-FDCC-M6_G17_s20221851806177_e20221851808550_c20221851809313.nc";
+
+    fn xy(points: &[FirePoint]) -> Vec<(isize, isize)> {
+        points.iter().map(|pnt| (pnt.x, pnt.y)).collect()
+    }
+
+    #[test]
+    fn test_extract_fire_points() {
+        let img = SatFireImage::open(test_file(FILE_WITH_DQF)).unwrap();
+        let points = img
+            .extract_fire_points_with_buffers(&mut ImageBuffers::new())
+            .unwrap();
+
+        assert_eq!(xy(&points), vec![(1, 1), (2, 1), (2, 2)]);
+        assert_eq!(points[0].pixel.power, 120.0);
+        assert_eq!(points[0].pixel.mask_flag, MaskCode(10));
+        assert!(points
+            .iter()
+            .all(|pnt| pnt.pixel.data_quality_flag == DataQualityFlagCode(0)));
+    }
+
+    #[test]
+    fn test_extract_fire_points_missing_dqf() {
+        let img = SatFireImage::open(test_file(FILE_WITHOUT_DQF)).unwrap();
+        let points = img
+            .extract_fire_points_with_buffers(&mut ImageBuffers::new())
+            .unwrap();
+
+        // Without quality control, the cloud contaminated pixel is kept too.
+        assert_eq!(xy(&points), vec![(1, 1), (2, 1), (0, 2), (2, 2)]);
+        assert_eq!(points[2].pixel.mask_flag, MaskCode(12));
+    }
+
+    #[test]
+    fn test_image_buffers_reused() {
+        fn addresses(buffers: &ImageBuffers) -> [(usize, usize); 5] {
+            [
+                (buffers.powers.as_ptr() as usize, buffers.powers.capacity()),
+                (buffers.areas.as_ptr() as usize, buffers.areas.capacity()),
+                (
+                    buffers.temperatures.as_ptr() as usize,
+                    buffers.temperatures.capacity(),
+                ),
+                (buffers.masks.as_ptr() as usize, buffers.masks.capacity()),
+                (buffers.dqfs.as_ptr() as usize, buffers.dqfs.capacity()),
+            ]
+        }
+
+        let img = SatFireImage::open(test_file(FILE_WITH_DQF)).unwrap();
+        let mut buffers = ImageBuffers::new();
+
+        let first = img.extract_fire_points_with_buffers(&mut buffers).unwrap();
+        let after_first = addresses(&buffers);
+        assert!(after_first.iter().all(|&(_, capacity)| capacity >= 12));
+
+        let second = img.extract_fire_points_with_buffers(&mut buffers).unwrap();
+        assert_eq!(addresses(&buffers), after_first);
+
+        assert_eq!(first.len(), second.len());
+        for (a, b) in first.iter().zip(second.iter()) {
+            assert_eq!((a.x, a.y), (b.x, b.y));
+            assert_eq!(a.pixel.power, b.pixel.power);
+            assert_eq!(a.pixel.area, b.pixel.area);
+            assert_eq!(a.pixel.temperature, b.pixel.temperature);
+            assert_eq!(a.pixel.mask_flag, b.pixel.mask_flag);
+            assert_eq!(a.pixel.data_quality_flag, b.pixel.data_quality_flag);
+            assert_eq!(a.pixel.scan_angle, b.pixel.scan_angle);
+            assert_eq!(
+                (a.pixel.ul.lat, a.pixel.ul.lon),
+                (b.pixel.ul.lat, b.pixel.ul.lon)
+            );
+            assert_eq!(
+                (a.pixel.lr.lat, a.pixel.lr.lon),
+                (b.pixel.lr.lat, b.pixel.lr.lon)
+            );
+        }
+    }
+}
@@ -1,7 +1,11 @@
 //! Geographic primitives specifically suited to the needs of this crate.
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Mul, Sub},
+};
 
 /// A coordinate consisting of a latitude and a longitude.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct Coord {
@@ -23,6 +27,72 @@ impl Coord {
 
         distance_squared <= (eps * eps)
     }
+
+    /// The great circle distance between these coordinates in meters.
+    ///
+    /// This treats the Earth as a sphere with the mean radius of the Earth, 6,371,008.8 m.
+    pub fn distance_haversine_m(&self, other: Coord) -> f64 {
+        great_circle_angle(*self, other) * EARTH_RADIUS_M
+    }
+
+    /// The initial bearing, in degrees clockwise from north, of the great circle path from this
+    /// coordinate to the other one.
+    ///
+    /// The result is in the range 0 to 360.
+    pub fn bearing_deg(&self, other: Coord) -> f64 {
+        let lat1 = self.lat.to_radians();
+        let lat2 = other.lat.to_radians();
+        let dlon = (other.lon - self.lon).to_radians();
+
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+}
+
+impl Add for Coord {
+    type Output = Coord;
+
+    fn add(self, rhs: Coord) -> Coord {
+        Coord {
+            lat: self.lat + rhs.lat,
+            lon: self.lon + rhs.lon,
+        }
+    }
+}
+
+impl Sub for Coord {
+    type Output = Coord;
+
+    fn sub(self, rhs: Coord) -> Coord {
+        Coord {
+            lat: self.lat - rhs.lat,
+            lon: self.lon - rhs.lon,
+        }
+    }
+}
+
+impl Mul<f64> for Coord {
+    type Output = Coord;
+
+    fn mul(self, rhs: f64) -> Coord {
+        Coord {
+            lat: self.lat * rhs,
+            lon: self.lon * rhs,
+        }
+    }
+}
+
+impl Div<f64> for Coord {
+    type Output = Coord;
+
+    fn div(self, rhs: f64) -> Coord {
+        Coord {
+            lat: self.lat / rhs,
+            lon: self.lon / rhs,
+        }
+    }
 }
 
 /// Represents a "square" area in latitude-longitude coordinates.
@@ -61,14 +131,122 @@ impl Default for BoundingBox {
     }
 }
 
+impl FromIterator<Coord> for BoundingBox {
+    /// Create the smallest box that contains all the coordinates.
+    ///
+    /// If there are no coordinates, this is the same as the default box.
+    fn from_iter<I: IntoIterator<Item = Coord>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(BoundingBox::default(), |bbox, coord| {
+                bbox.union(&BoundingBox {
+                    ll: coord,
+                    ur: coord,
+                })
+            })
+    }
+}
+
 impl BoundingBox {
+    /// Get the smallest bounding box that contains both of these boxes.
+    ///
+    /// Empty boxes, such as the default box or one with its corners inverted, are ignored. So the
+    /// union of an empty box and another box is just the other box.
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        if other.is_empty() {
+            return *self;
+        }
+
+        if self.is_empty() {
+            return *other;
+        }
+
+        BoundingBox {
+            ll: Coord {
+                lat: self.ll.lat.min(other.ll.lat),
+                lon: self.ll.lon.min(other.ll.lon),
+            },
+            ur: Coord {
+                lat: self.ur.lat.max(other.ur.lat),
+                lon: self.ur.lon.max(other.ur.lon),
+            },
+        }
+    }
+
+    /// Get the box where these boxes overlap, or `None` if they don't overlap.
+    ///
+    /// Boxes that only share an edge or corner overlap in a box with zero width or height.
+    pub fn intersection(&self, other: &BoundingBox) -> Option<BoundingBox> {
+        if self.is_empty() || other.is_empty() || !self.overlap(other, 0.0) {
+            return None;
+        }
+
+        Some(BoundingBox {
+            ll: Coord {
+                lat: self.ll.lat.max(other.ll.lat),
+                lon: self.ll.lon.max(other.ll.lon),
+            },
+            ur: Coord {
+                lat: self.ur.lat.min(other.ur.lat),
+                lon: self.ur.lon.min(other.ur.lon),
+            },
+        })
+    }
+
+    /// Grow the box by a margin on every side.
+    ///
+    /// The box is expanded by `lat_margin` degrees to the north and south, and `lon_margin` degrees
+    /// to the east and west. The result is clamped to the valid range of latitudes (-90 to 90) and
+    /// longitudes (-180 to 180).
+    pub fn expanded_by(&self, lat_margin: f64, lon_margin: f64) -> BoundingBox {
+        BoundingBox {
+            ll: Coord {
+                lat: (self.ll.lat - lat_margin).clamp(-90.0, 90.0),
+                lon: (self.ll.lon - lon_margin).clamp(-180.0, 180.0),
+            },
+            ur: Coord {
+                lat: (self.ur.lat + lat_margin).clamp(-90.0, 90.0),
+                lon: (self.ur.lon + lon_margin).clamp(-180.0, 180.0),
+            },
+        }
+    }
+
+    /// Get the point in the middle of the box.
+    pub fn center(&self) -> Coord {
+        (self.ll + self.ur) / 2.0
+    }
+
+    /// Approximate the area of the box in square kilometers.
+    ///
+    /// Lines of longitude converge towards the poles, so the east-west width is scaled by the
+    /// cosine of the mean latitude of the box.
+    pub fn area_km2(&self) -> f64 {
+        let km_per_degree = (EARTH_RADIUS_M / 1000.0).to_radians();
+
+        let height = (self.ur.lat - self.ll.lat) * km_per_degree;
+        let width =
+            (self.ur.lon - self.ll.lon) * km_per_degree * self.center().lat.to_radians().cos();
+
+        height * width
+    }
+
+    /// A box is empty if it has non-finite corners or if the lower left corner is above or to the
+    /// right of the upper right corner.
+    fn is_empty(&self) -> bool {
+        let finite = self.ll.lat.is_finite()
+            && self.ll.lon.is_finite()
+            && self.ur.lat.is_finite()
+            && self.ur.lon.is_finite();
+
+        !finite || self.ll.lat > self.ur.lat || self.ll.lon > self.ur.lon
+    }
+
     /// Check to see if a Coord is inside of a BoundingBox.
     ///
     /// # Arguments
     /// * `box` - is the bounding box in question.
     /// * `coord` - is the coordinate, or point, in question.
     /// * `eps` - is a fuzzy factor. Any point `eps` close to the box will be considered internal as
-    ///    well. If `eps` is 0.0, then the comparison is exact.
+    ///   well. If `eps` is 0.0, then the comparison is exact.
     ///
     /// # Return
     ///
@@ -85,7 +263,7 @@ impl BoundingBox {
     /// # Arguments
     /// * `other` - is the other box to compare to.
     /// * `eps` - is a fuzzy factor. In any point comparisons, any point within 'eps' close to the
-    ///    box `self` will be considered as overlapping.
+    ///   box `self` will be considered as overlapping.
     pub fn overlap(&self, other: &BoundingBox, eps: f64) -> bool {
         let BoundingBox {
             ll: Coord {
@@ -158,22 +336,57 @@ pub trait Geo {
 }
 
 pub(crate) fn triangle_centroid(v1: Coord, v2: Coord, v3: Coord) -> Coord {
-    let avg_lat = (v1.lat + v2.lat + v3.lat) / 3.0;
-    let avg_lon = (v1.lon + v2.lon + v3.lon) / 3.0;
+    (v1 + v2 + v3) / 3.0
+}
 
-    Coord {
-        lat: avg_lat,
-        lon: avg_lon,
-    }
+/// Radius of the Earth in meters used for geometric calculations.
+///
+/// This is the IUGG mean radius of the Earth, and these calculations treat the Earth as a sphere
+/// with this radius.
+pub(crate) const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// The central angle between two coordinates on a sphere in radians.
+///
+/// This uses the haversine formula, which is well conditioned for the small distances between
+/// the corners of a satellite pixel.
+pub(crate) fn great_circle_angle(v1: Coord, v2: Coord) -> f64 {
+    let lat1 = v1.lat.to_radians();
+    let lat2 = v2.lat.to_radians();
+    let dlat = lat2 - lat1;
+    let dlon = (v2.lon - v1.lon).to_radians();
+
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+
+    2.0 * a.sqrt().min(1.0).asin()
+}
+
+/// The area of a triangle on the unit sphere, in steradians.
+///
+/// The sides of the triangle are great circle arcs and the area is the spherical excess
+/// calculated with L'Huilier's theorem.
+pub(crate) fn spherical_triangle_area(v1: Coord, v2: Coord, v3: Coord) -> f64 {
+    let a = great_circle_angle(v2, v3);
+    let b = great_circle_angle(v1, v3);
+    let c = great_circle_angle(v1, v2);
+    let s = (a + b + c) / 2.0;
+
+    let product =
+        (s / 2.0).tan() * ((s - a) / 2.0).tan() * ((s - b) / 2.0).tan() * ((s - c) / 2.0).tan();
+
+    // Rounding can make the product slightly negative for degenerate triangles.
+    4.0 * product.max(0.0).sqrt().atan()
 }
 
 /*-------------------------------------------------------------------------------------------------
  *                                    Helper types and functions
  *-----------------------------------------------------------------------------------------------*/
 
+/// A line segment between two coordinates.
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct Line {
+pub struct Line {
+    /// The starting point of the segment.
     pub start: Coord,
+    /// The ending point of the segment.
     pub end: Coord,
 }
 
@@ -189,6 +402,34 @@ impl Line {
         (num * num / denom2) <= eps2
     }
 
+    /// Determine which side of the (infinitely extended) line a coordinate lies on.
+    ///
+    /// Positive values are to the left when looking from `start` towards `end`, negative values
+    /// are to the right, and zero is on the line.
+    pub(crate) fn side_of(&self, coord: Coord) -> f64 {
+        let Line { start: p1, end: p2 } = self;
+
+        (p2.lon - p1.lon) * (coord.lat - p1.lat) - (p2.lat - p1.lat) * (coord.lon - p1.lon)
+    }
+
+    /// Find where the segment from `p0` to `p1` crosses this line extended infinitely in both
+    /// directions.
+    ///
+    /// This assumes the caller already knows `p0` and `p1` are on opposite sides of the line.
+    fn extended_intersection(&self, p0: Coord, p1: Coord) -> Coord {
+        let s0 = self.side_of(p0);
+        let s1 = self.side_of(p1);
+        let t = s0 / (s0 - s1);
+
+        p0 + (p1 - p0) * t
+    }
+
+    /// Find the point where two line segments intersect.
+    ///
+    /// Returns `None` if the segments don't intersect, or if they are parallel or colinear since
+    /// there is no single point of intersection in those cases. Segments where more than one end
+    /// point lies within `eps` of the other segment, such as two segments that share an end point,
+    /// are treated as colinear. The comparisons are done in lat-lon space.
     pub fn intersect(&self, other: Line, eps: f64) -> Option<IntersectResult> {
         // Check if they are nearly co-linear
         let mut num_close = 0;
@@ -289,11 +530,114 @@ impl Line {
     }
 }
 
-pub(crate) struct IntersectResult {
+/// The result of intersecting two line segments with [Line::intersect].
+#[derive(Debug, Clone, Copy)]
+pub struct IntersectResult {
+    /// The point where the segments intersect.
     pub intersection: Coord,
+    /// `true` if the intersection is within `eps` of an end point of both segments.
     pub intersect_is_endpoints: bool,
 }
 
+/// Calculate the signed area of a simple polygon in the lat-lon space using the shoelace formula.
+///
+/// The polygon is described by its vertices, and it should NOT repeat the first vertex at the end.
+/// The result is positive if the vertices are ordered counter-clockwise (with longitude as the x
+/// axis and latitude as the y axis) and negative if they are clockwise. The units are square
+/// degrees.
+pub(crate) fn polygon_signed_area(vertices: &[Coord]) -> f64 {
+    if vertices.len() < 3 {
+        return 0.0;
+    }
+
+    let mut sum = 0.0;
+    for (i, v1) in vertices.iter().enumerate() {
+        let v2 = vertices[(i + 1) % vertices.len()];
+        sum += v1.lon * v2.lat - v2.lon * v1.lat;
+    }
+
+    sum / 2.0
+}
+
+/// Check if a coordinate is inside a simple polygon in the lat-lon space.
+///
+/// The polygon is described by its vertices, and it should NOT repeat the first vertex at the end.
+/// Either orientation works. This uses the even-odd rule, so the result for a coordinate on an
+/// edge of the polygon could go either way.
+pub(crate) fn polygon_contains_coord(vertices: &[Coord], coord: Coord) -> bool {
+    let mut inside = false;
+
+    for (i, v1) in vertices.iter().enumerate() {
+        let v2 = vertices[(i + 1) % vertices.len()];
+
+        // Count the edges crossed by a ray going east from the coordinate.
+        if (v1.lat > coord.lat) != (v2.lat > coord.lat) {
+            let lon_crossing =
+                v1.lon + (coord.lat - v1.lat) * (v2.lon - v1.lon) / (v2.lat - v1.lat);
+            if coord.lon < lon_crossing {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+/// Clip the convex polygon `subject` so only the part inside the convex polygon `clip` remains.
+///
+/// This is the Sutherland-Hodgman algorithm. Both polygons are described by their vertices without
+/// repeating the first vertex at the end, and they may be ordered either clockwise or
+/// counter-clockwise. Consecutive vertices in the output that are within `eps` of each other are
+/// collapsed into a single vertex. The returned polygon has the same orientation as `clip`, and it
+/// will have fewer than 3 vertices if there is no overlap.
+pub(crate) fn clip_convex_polygon(subject: &[Coord], clip: &[Coord], eps: f64) -> Vec<Coord> {
+    // Which side of the clip edge is inside depends on the orientation of the clip polygon.
+    let orientation = polygon_signed_area(clip).signum();
+
+    let mut output: Vec<Coord> = subject.to_vec();
+    let mut input: Vec<Coord> = Vec::with_capacity(subject.len() + clip.len());
+
+    for (i, &edge_start) in clip.iter().enumerate() {
+        if output.is_empty() {
+            break;
+        }
+
+        let edge = Line {
+            start: edge_start,
+            end: clip[(i + 1) % clip.len()],
+        };
+
+        std::mem::swap(&mut input, &mut output);
+        output.clear();
+
+        let mut prev = input[input.len() - 1];
+        let mut prev_side = edge.side_of(prev) * orientation;
+        for &curr in &input {
+            let curr_side = edge.side_of(curr) * orientation;
+
+            if curr_side >= 0.0 {
+                if prev_side < 0.0 {
+                    output.push(edge.extended_intersection(prev, curr));
+                }
+                output.push(curr);
+            } else if prev_side >= 0.0 {
+                output.push(edge.extended_intersection(prev, curr));
+            }
+
+            prev = curr;
+            prev_side = curr_side;
+        }
+    }
+
+    // Remove any duplicate vertices created where the clipping passed through a vertex.
+    output.dedup_by(|a, b| a.is_close(*b, eps));
+    while output.len() > 1 && output[0].is_close(output[output.len() - 1], eps) {
+        output.pop();
+    }
+
+    output
+}
+
 mod hilbert_rtree;
 pub(crate) use hilbert_rtree::Hilbert2DRTreeView;
 
@@ -320,6 +664,201 @@ mod test {
         assert!(!left.is_close(right, 1.0e-8));
     }
 
+    #[test]
+    fn test_bounding_box_union_and_collect() {
+        let b1 = BoundingBox {
+            ll: Coord { lat: 0.0, lon: 0.0 },
+            ur: Coord { lat: 1.0, lon: 1.0 },
+        };
+        let b2 = BoundingBox {
+            ll: Coord {
+                lat: 2.5,
+                lon: -3.5,
+            },
+            ur: Coord { lat: 3.5, lon: 0.5 },
+        };
+        let inverted = BoundingBox {
+            ll: Coord { lat: 5.0, lon: 5.0 },
+            ur: Coord { lat: 4.0, lon: 4.0 },
+        };
+
+        let u = b1.union(&b2);
+        assert_eq!(
+            (u.ll.lat, u.ll.lon, u.ur.lat, u.ur.lon),
+            (0.0, -3.5, 3.5, 1.0)
+        );
+
+        let u = b1.union(&BoundingBox::default());
+        assert_eq!(
+            (u.ll.lat, u.ll.lon, u.ur.lat, u.ur.lon),
+            (0.0, 0.0, 1.0, 1.0)
+        );
+
+        let u = inverted.union(&b1);
+        assert_eq!(
+            (u.ll.lat, u.ll.lon, u.ur.lat, u.ur.lon),
+            (0.0, 0.0, 1.0, 1.0)
+        );
+
+        let coords = [
+            Coord { lat: 1.0, lon: 2.0 },
+            Coord {
+                lat: -1.0,
+                lon: 4.0,
+            },
+            Coord { lat: 3.0, lon: 3.0 },
+        ];
+        let b: BoundingBox = coords.into_iter().collect();
+        assert_eq!(
+            (b.ll.lat, b.ll.lon, b.ur.lat, b.ur.lon),
+            (-1.0, 2.0, 3.0, 4.0)
+        );
+
+        let empty: BoundingBox = std::iter::empty().collect();
+        assert!(!empty.overlap(&b1, 1.0e-9));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_bounding_box_intersection() {
+        let b1 = BoundingBox { ll: Coord { lat: 0.0, lon: 0.0 }, ur: Coord { lat: 2.0, lon: 2.0 } };
+        let b2 = BoundingBox { ll: Coord { lat: 1.0, lon: 1.5 }, ur: Coord { lat: 3.0, lon: 4.0 } };
+        let b3 = BoundingBox { ll: Coord { lat: 2.0, lon: 0.0 }, ur: Coord { lat: 3.0, lon: 1.0 } };
+        let b4 = BoundingBox { ll: Coord { lat: 5.0, lon: 5.0 }, ur: Coord { lat: 6.0, lon: 6.0 } };
+
+        let i = b1.intersection(&b2).unwrap();
+        assert_eq!((i.ll.lat, i.ll.lon, i.ur.lat, i.ur.lon), (1.0, 1.5, 2.0, 2.0));
+
+        let i = b2.intersection(&b1).unwrap();
+        assert_eq!((i.ll.lat, i.ll.lon, i.ur.lat, i.ur.lon), (1.0, 1.5, 2.0, 2.0));
+
+        // Sharing an edge
+        let i = b1.intersection(&b3).unwrap();
+        assert_eq!((i.ll.lat, i.ll.lon, i.ur.lat, i.ur.lon), (2.0, 0.0, 2.0, 1.0));
+
+        assert!(b1.intersection(&b4).is_none());
+        assert!(b1.intersection(&BoundingBox::default()).is_none());
+    }
+
+    #[test]
+    fn test_bounding_box_center_and_area() {
+        let equator = BoundingBox {
+            ll: Coord {
+                lat: -0.5,
+                lon: 0.0,
+            },
+            ur: Coord { lat: 0.5, lon: 1.0 },
+        };
+        let mid_lat = BoundingBox {
+            ll: Coord {
+                lat: 44.5,
+                lon: -120.0,
+            },
+            ur: Coord {
+                lat: 45.5,
+                lon: -119.0,
+            },
+        };
+
+        let center = mid_lat.center();
+        assert_eq!((center.lat, center.lon), (45.0, -119.5));
+
+        // A 1 degree box at the equator is about 111.2 km on each side.
+        let naive = 111.195 * 111.195;
+        assert!((equator.area_km2() - naive).abs() / naive < 1.0e-3);
+
+        // Lines of longitude converge, so the area shrinks near 45N.
+        let area = mid_lat.area_km2();
+        assert!(area < naive);
+        assert!((area - naive * 45.0_f64.to_radians().cos()).abs() / area < 1.0e-3);
+    }
+
+    #[test]
+    fn test_bounding_box_expanded_by() {
+        let bbox = BoundingBox {
+            ll: Coord {
+                lat: 44.0,
+                lon: -116.5,
+            },
+            ur: Coord {
+                lat: 49.5,
+                lon: -104.0,
+            },
+        };
+
+        let b = bbox.expanded_by(0.5, 1.0);
+        assert_eq!(
+            (b.ll.lat, b.ll.lon, b.ur.lat, b.ur.lon),
+            (43.5, -117.5, 50.0, -103.0)
+        );
+
+        let polar = BoundingBox {
+            ll: Coord {
+                lat: 85.0,
+                lon: 175.0,
+            },
+            ur: Coord {
+                lat: 89.0,
+                lon: 179.0,
+            },
+        };
+
+        let b = polar.expanded_by(2.0, 2.0);
+        assert_eq!(
+            (b.ll.lat, b.ll.lon, b.ur.lat, b.ur.lon),
+            (83.0, 173.0, 90.0, 180.0)
+        );
+    }
+
+    #[test]
+    fn test_coord_arithmetic() {
+        let a = Coord {
+            lat: 45.0,
+            lon: -120.0,
+        };
+        let b = Coord { lat: 1.0, lon: 2.0 };
+
+        let sum = a + b;
+        assert_eq!((sum.lat, sum.lon), (46.0, -118.0));
+
+        let diff = a - b;
+        assert_eq!((diff.lat, diff.lon), (44.0, -122.0));
+
+        let scaled = b * 2.0;
+        assert_eq!((scaled.lat, scaled.lon), (2.0, 4.0));
+
+        let avg = (a + b) / 2.0;
+        assert_eq!((avg.lat, avg.lon), (23.0, -59.0));
+    }
+
+    #[test]
+    fn test_coord_distance_and_bearing() {
+        let start = Coord {
+            lat: 45.0,
+            lon: -120.0,
+        };
+        let north = Coord {
+            lat: 46.0,
+            lon: -120.0,
+        };
+        let east = Coord { lat: 0.0, lon: 1.0 };
+        let origin = Coord { lat: 0.0, lon: 0.0 };
+
+        // One degree of latitude is about 111.2 km
+        let dist = start.distance_haversine_m(north);
+        assert!((dist - 111_195.0).abs() < 1.0, "{}", dist);
+        assert!((north.distance_haversine_m(start) - dist).abs() < 1.0e-6);
+        assert_eq!(start.distance_haversine_m(start), 0.0);
+
+        // So is one degree of longitude at the equator
+        assert!((origin.distance_haversine_m(east) - 111_195.0).abs() < 1.0);
+
+        assert!(start.bearing_deg(north).abs() < 1.0e-9);
+        assert!((north.bearing_deg(start) - 180.0).abs() < 1.0e-9);
+        assert!((origin.bearing_deg(east) - 90.0).abs() < 1.0e-9);
+        assert!((east.bearing_deg(origin) - 270.0).abs() < 1.0e-9);
+    }
+
     #[test]
     fn test_default_bounding_boxes_do_not_overlap() {
         let b1 = BoundingBox::default();
@@ -363,4 +902,24 @@ mod test {
 
         assert!(!b5.overlap(&b6, 1.0e-9));
     }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_line_intersect() {
+        let l1 = Line { start: Coord { lat: 0.0, lon: 0.0 }, end: Coord { lat: 1.0, lon: 1.0 } };
+        let l2 = Line { start: Coord { lat: 1.0, lon: 0.0 }, end: Coord { lat: 0.0, lon: 1.0 } };
+        let l3 = Line { start: Coord { lat: 1.0, lon: 1.0 }, end: Coord { lat: 2.0, lon: 0.0 } };
+        let l4 = Line { start: Coord { lat: 0.0, lon: 1.0 }, end: Coord { lat: 1.0, lon: 2.0 } };
+
+        // Crossing in the middle
+        let res = l1.intersect(l2, 1.0e-9).unwrap();
+        assert!(res.intersection.is_close(Coord { lat: 0.5, lon: 0.5 }, 1.0e-12));
+        assert!(!res.intersect_is_endpoints);
+
+        // Meeting at a shared end point
+        assert!(l1.intersect(l3, 1.0e-9).is_none());
+
+        // Parallel
+        assert!(l1.intersect(l4, 1.0e-9).is_none());
+    }
 }
@@ -316,27 +316,9 @@ impl<'a, T: Geo> Hilbert2DRTreeView<'a, T> {
     }
 
     fn build_domain(data: &[T]) -> BoundingBox {
-        let mut mbr = BoundingBox {
-            ll: Coord {
-                lat: f64::INFINITY,
-                lon: f64::INFINITY,
-            },
-            ur: Coord {
-                lat: -f64::INFINITY,
-                lon: -f64::INFINITY,
-            },
-        };
-
-        for item in data {
-            let item_rect = item.bounding_box();
-
-            mbr.ll.lat = mbr.ll.lat.min(item_rect.ll.lat);
-            mbr.ll.lon = mbr.ll.lon.min(item_rect.ll.lon);
-            mbr.ur.lat = mbr.ur.lat.max(item_rect.ur.lat);
-            mbr.ur.lon = mbr.ur.lon.max(item_rect.ur.lon);
-        }
-
-        mbr
+        data.iter().fold(BoundingBox::default(), |mbr, item| {
+            mbr.union(&item.bounding_box())
+        })
     }
 }
 
@@ -426,12 +408,9 @@ impl HilbertCurve {
                 y ^= t;
             }
 
+            // Exchanging the low bits of x with itself would do nothing, so there is no else.
             if (x & q) != 0 {
                 x ^= p;
-            } else {
-                t = (x ^ x) & p;
-                x ^= t;
-                x ^= t;
             }
             q <<= 1;
         }
@@ -455,12 +434,9 @@ impl HilbertCurve {
         let mut q = m;
         while q > 1 {
             let p = q - 1;
+            // Exchanging the low bits of x with itself would do nothing, so there is no else.
             if (x & q) != 0 {
                 x ^= p;
-            } else {
-                let t = (x ^ x) & p;
-                x ^= t;
-                x ^= t;
             }
 
             if (y & q) != 0 {
@@ -608,9 +584,7 @@ mod test {
         ];
 
         let hc = HilbertCurve::new(1, domain);
-        for i in 0..n1_pairs.len() {
-            let hilbert_dist = n1_pairs[i].1;
-            let coord = n1_pairs[i].0;
+        for &(coord, hilbert_dist) in n1_pairs.iter() {
 
             let hd_calc = hc.translate_to_curve_distance(coord);
 
@@ -634,9 +608,7 @@ mod test {
         ];
 
         let hc = HilbertCurve::new(1, domain);
-        for i in 0..n1_pairs_b.len() {
-            let hilbert_dist = n1_pairs_b[i].1;
-            let coord = n1_pairs_b[i].0;
+        for &(coord, hilbert_dist) in n1_pairs_b.iter() {
 
             let hd_calc = hc.translate_to_curve_distance(coord);
 
@@ -673,9 +645,7 @@ mod test {
         ];
 
         let hc = HilbertCurve::new(2, domain);
-        for i in 0..n2_pairs.len() {
-            let hilbert_dist = n2_pairs[i].1;
-            let coord = n2_pairs[i].0;
+        for &(coord, hilbert_dist) in n2_pairs.iter() {
 
             let hd_calc = hc.translate_to_curve_distance(coord);
 
@@ -738,11 +708,11 @@ mod test {
         rects
     }
 
-    fn test_bb_for_hits(mut rectangles: &mut [LabeledBB], bbox: BoundingBox, num_hits: usize) {
+    fn test_bb_for_hits(rectangles: &mut [LabeledBB], bbox: BoundingBox, num_hits: usize) {
         println!("Target Area: {} Expected Hits: {}", bbox, num_hits);
 
         // Create the view.
-        let mut view = Hilbert2DRTreeView::build_for(&mut rectangles, None).unwrap();
+        let mut view = Hilbert2DRTreeView::build_for(rectangles, None).unwrap();
 
         // Count the hits.
         let hits = view.foreach(bbox, 0, |labeled_rect, _rect_idx, hits_so_far| {
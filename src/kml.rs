@@ -7,9 +7,10 @@
 //! for this implementation I'm only implementing the parts I need with a focus on a more streaming
 //! type API. That means the user is responsible for closing all tags.
 
-use crate::SatFireResult;
+use crate::{BoundingBox, SatFireResult};
 use chrono::{DateTime, Utc};
 use std::{
+    borrow::Cow,
     fs::File,
     io::{BufWriter, Write},
     path::Path,
@@ -19,12 +20,26 @@ use zip::ZipWriter;
 pub struct KmzFile(ZipWriter<BufWriter<File>>);
 
 impl KmzFile {
+    /// The compression level used by [KmzFile::new], the maximum.
+    pub const DEFAULT_COMPRESSION_LEVEL: i32 = 9;
+
     pub fn new<P: AsRef<Path>>(pth: P) -> SatFireResult<Self> {
+        Self::with_compression(pth, Self::DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Create a KMZ file using the specified deflate compression level.
+    ///
+    /// The level must be from 0 (no compression, fastest) to 9 (best compression, slowest).
+    pub fn with_compression<P: AsRef<Path>>(pth: P, level: i32) -> SatFireResult<Self> {
+        if !(0..=9).contains(&level) {
+            return Err(format!("invalid KMZ compression level {}, must be 0 - 9", level).into());
+        }
+
         let p = pth.as_ref();
 
         let f = std::fs::File::create(p)?;
         let mut kmz = ZipWriter::new(BufWriter::new(f));
-        let kmz_opts = zip::write::FileOptions::default().compression_level(Some(9));
+        let kmz_opts = zip::write::FileOptions::default().compression_level(Some(level));
         kmz.start_file("doc.kml", kmz_opts)?;
         let mut new = KmzFile(kmz);
         new.start_document()?;
@@ -44,26 +59,37 @@ impl Drop for KmzFile {
     }
 }
 
-pub struct KmlFile(BufWriter<File>);
+/// A KML document written to a file, or any other output that implements `Write`.
+pub struct KmlFile<W: Write = BufWriter<File>>(W);
 
 impl KmlFile {
     pub fn new<P: AsRef<Path>>(pth: P) -> SatFireResult<Self> {
         let p = pth.as_ref();
 
         let f = std::fs::File::create(p)?;
-        let mut new = KmlFile(BufWriter::new(f));
+        KmlFile::from_writer(BufWriter::new(f))
+    }
+}
+
+impl<W: Write> KmlFile<W> {
+    /// Start a KML document that is written to `output`.
+    ///
+    /// The document is finished when this value is dropped, so to write into a buffer pass a
+    /// mutable reference to it, e.g. `&mut Vec<u8>`, and use the buffer after dropping this.
+    pub fn from_writer(output: W) -> SatFireResult<Self> {
+        let mut new = KmlFile(output);
         new.start_document()?;
         Ok(new)
     }
 }
 
-impl KmlWriter for KmlFile {
+impl<W: Write> KmlWriter for KmlFile<W> {
     fn output(&mut self) -> &mut dyn Write {
         &mut self.0
     }
 }
 
-impl Drop for KmlFile {
+impl<W: Write> Drop for KmlFile<W> {
     fn drop(&mut self) {
         self.finish_document();
     }
@@ -113,7 +139,7 @@ pub trait KmlWriter {
         self.output().write_all("<Folder>\n".as_bytes())?;
 
         if let Some(name) = name {
-            writeln!(self.output(), "<name>{}</name>", name)?;
+            writeln!(self.output(), "<name>{}</name>", xml_escape(name))?;
         }
 
         if let Some(description) = description {
@@ -143,7 +169,7 @@ pub trait KmlWriter {
         writeln!(self.output(), "<Placemark>")?;
 
         if let Some(name) = name {
-            writeln!(self.output(), "<name>{}</name>", name)?;
+            writeln!(self.output(), "<name>{}</name>", xml_escape(name))?;
         }
 
         if let Some(description) = description {
@@ -151,7 +177,11 @@ pub trait KmlWriter {
         }
 
         if let Some(style_url) = style_url {
-            writeln!(self.output(), "<styleUrl>{}</styleUrl>", style_url)?;
+            writeln!(
+                self.output(),
+                "<styleUrl>{}</styleUrl>",
+                xml_escape(style_url)
+            )?;
         }
 
         Ok(())
@@ -166,7 +196,7 @@ pub trait KmlWriter {
     /// Start a style definition.
     fn start_style(&mut self, style_id: Option<&str>) -> SatFireResult<()> {
         if let Some(style_id) = style_id {
-            writeln!(self.output(), "<Style id=\"{}\">", style_id)?;
+            writeln!(self.output(), "<Style id=\"{}\">", xml_escape(style_id))?;
         } else {
             writeln!(self.output(), "<Style>")?;
         }
@@ -191,7 +221,7 @@ pub trait KmlWriter {
         writeln!(self.output(), "<PolyStyle>")?;
 
         if let Some(color) = color {
-            writeln!(self.output(), "<color>{}</color>", color)?;
+            writeln!(self.output(), "<color>{}</color>", xml_escape(color))?;
             writeln!(self.output(), "<colorMode>normal</colorMode>")?;
         } else {
             writeln!(self.output(), "<colorMode>random</colorMode>")?;
@@ -207,6 +237,44 @@ pub trait KmlWriter {
         Ok(())
     }
 
+    /// Create a LineStyle element.
+    ///
+    /// These should ONLY go inside a style element.
+    fn create_line_style(&mut self, color: Option<&str>, width: f64) -> SatFireResult<()> {
+        writeln!(self.output(), "<LineStyle>")?;
+
+        if let Some(color) = color {
+            writeln!(self.output(), "<color>{}</color>", xml_escape(color))?;
+            writeln!(self.output(), "<colorMode>normal</colorMode>")?;
+        } else {
+            writeln!(self.output(), "<colorMode>random</colorMode>")?;
+        }
+
+        writeln!(self.output(), "<width>{}</width>", width)?;
+
+        writeln!(self.output(), "</LineStyle>")?;
+        Ok(())
+    }
+
+    /// Create a LabelStyle element.
+    ///
+    /// A scale of 0 hides the labels entirely. These should ONLY go inside a style element.
+    fn create_label_style(&mut self, color: Option<&str>, scale: f64) -> SatFireResult<()> {
+        writeln!(self.output(), "<LabelStyle>")?;
+
+        if let Some(color) = color {
+            writeln!(self.output(), "<color>{}</color>", xml_escape(color))?;
+            writeln!(self.output(), "<colorMode>normal</colorMode>")?;
+        } else {
+            writeln!(self.output(), "<colorMode>random</colorMode>")?;
+        }
+
+        writeln!(self.output(), "<scale>{}</scale>", scale.max(0.0))?;
+
+        writeln!(self.output(), "</LabelStyle>")?;
+        Ok(())
+    }
+
     /// Create an IconStyle element.
     fn create_icon_style(&mut self, icon_url: Option<&str>, scale: f64) -> SatFireResult<()> {
         writeln!(self.output(), "<IconStyle>")?;
@@ -218,7 +286,11 @@ pub trait KmlWriter {
         }
 
         if let Some(icon_url) = icon_url {
-            writeln!(self.output(), "<Icon><href>{}</href></Icon>", icon_url)?;
+            writeln!(
+                self.output(),
+                "<Icon><href>{}</href></Icon>",
+                xml_escape(icon_url)
+            )?;
         } else {
             writeln!(self.output(), "<Icon/>")?;
         }
@@ -316,6 +388,24 @@ pub trait KmlWriter {
         Ok(())
     }
 
+    /// Start a polygon inner ring, the boundary of a hole.
+    ///
+    /// This should only be used inside a Polygon element, after the outer ring.
+    ///
+    fn polygon_start_inner_ring(&mut self) -> SatFireResult<()> {
+        self.output().write_all("<innerBoundaryIs>\n".as_bytes())?;
+        Ok(())
+    }
+
+    /// End a polygon inner ring.
+    ///
+    /// This should only be used inside a Polygon element.
+    ///
+    fn polygon_finish_inner_ring(&mut self) -> SatFireResult<()> {
+        self.output().write_all("</innerBoundaryIs>\n".as_bytes())?;
+        Ok(())
+    }
+
     /// Start a LinearRing.
     fn start_linear_ring(&mut self) -> SatFireResult<()> {
         self.output()
@@ -349,4 +439,260 @@ pub trait KmlWriter {
         )?;
         Ok(())
     }
+
+    /// Write out a GroundOverlay element that drapes an image over the area in the bounding box.
+    ///
+    /// The href is usually the path of an image bundled in the same KMZ file. The color is in the
+    /// KML aabbggrr format and can be used to make the image translucent.
+    fn ground_overlay(
+        &mut self,
+        href: &str,
+        bbox: BoundingBox,
+        color: Option<&str>,
+    ) -> SatFireResult<()> {
+        writeln!(self.output(), "<GroundOverlay>")?;
+
+        if let Some(color) = color {
+            writeln!(self.output(), "<color>{}</color>", xml_escape(color))?;
+        }
+
+        writeln!(
+            self.output(),
+            "<Icon><href>{}</href></Icon>",
+            xml_escape(href)
+        )?;
+
+        writeln!(self.output(), "<LatLonBox>")?;
+        writeln!(self.output(), "<north>{}</north>", bbox.ur.lat)?;
+        writeln!(self.output(), "<south>{}</south>", bbox.ll.lat)?;
+        writeln!(self.output(), "<east>{}</east>", bbox.ur.lon)?;
+        writeln!(self.output(), "<west>{}</west>", bbox.ll.lon)?;
+        writeln!(self.output(), "</LatLonBox>")?;
+
+        writeln!(self.output(), "</GroundOverlay>")?;
+        Ok(())
+    }
+}
+
+/// Escape the characters that have special meaning in XML text and attribute values.
+///
+/// This should not be used on text written inside a CDATA section.
+fn xml_escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len() + 16);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_kml_file_creates_document() {
+        let path =
+            std::env::temp_dir().join(format!("satfire_kml_test_{}.kml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        {
+            let mut kml = KmlFile::new(&path).unwrap();
+            kml.start_folder(Some("Test"), None, false).unwrap();
+            kml.finish_folder().unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(contents.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(contents.contains("<name>Test</name>"));
+        assert!(contents.ends_with("</Document>\n</kml>\n"));
+    }
+
+    #[test]
+    fn test_kml_header_footer_and_timespan_use_newlines() {
+        use chrono::TimeZone;
+
+        let path = std::env::temp_dir().join(format!(
+            "satfire_kml_newline_test_{}.kml",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        {
+            let mut kml = KmlFile::new(&path).unwrap();
+            kml.timespan(
+                Utc.ymd(2022, 7, 1).and_hms(12, 0, 0),
+                Utc.ymd(2022, 7, 1).and_hms(18, 30, 0),
+            )
+            .unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(!contents.contains("\\n"));
+        assert!(contents.contains(concat!(
+            "<Document>\n",
+            "<TimeSpan>\n",
+            "<begin>2022-07-01T12:00:00.000Z</begin>\n",
+            "<end>2022-07-01T18:30:00.000Z</end>\n",
+            "</TimeSpan>\n",
+            "</Document>\n",
+            "</kml>\n"
+        )));
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(xml_escape("G17 FDCF"), "G17 FDCF");
+        assert!(matches!(xml_escape("G17 FDCF"), Cow::Borrowed(_)));
+        assert_eq!(
+            xml_escape(r#"Fire <A> & "B's""#),
+            "Fire &lt;A&gt; &amp; &quot;B&apos;s&quot;"
+        );
+    }
+
+    #[test]
+    fn test_kml_file_from_writer() {
+        let mut buf: Vec<u8> = vec![];
+
+        {
+            let mut kml = KmlFile::from_writer(&mut buf).unwrap();
+            kml.start_placemark(Some("Fire"), None, None).unwrap();
+            kml.create_point(45.0, -120.0, 0.0).unwrap();
+            kml.finish_placemark().unwrap();
+        }
+
+        let contents = String::from_utf8(buf).unwrap();
+        assert!(contents.contains("<name>Fire</name>"));
+        assert!(contents.contains("<coordinates>-120,45,0</coordinates>"));
+        assert!(contents.ends_with("</Document>\n</kml>\n"));
+    }
+
+    #[test]
+    fn test_kml_line_style() {
+        let mut buf: Vec<u8> = vec![];
+
+        {
+            let mut kml = KmlFile::from_writer(&mut buf).unwrap();
+            kml.start_style(Some("outline")).unwrap();
+            kml.create_line_style(Some("80FFFFFF"), 0.5).unwrap();
+            kml.finish_style().unwrap();
+            kml.start_style(None).unwrap();
+            kml.create_line_style(None, 2.0).unwrap();
+            kml.finish_style().unwrap();
+        }
+
+        let contents = String::from_utf8(buf).unwrap();
+        assert!(contents.contains(concat!(
+            "<Style id=\"outline\">\n",
+            "<LineStyle>\n",
+            "<color>80FFFFFF</color>\n",
+            "<colorMode>normal</colorMode>\n",
+            "<width>0.5</width>\n",
+            "</LineStyle>\n",
+            "</Style>\n"
+        )));
+        assert!(contents.contains("<colorMode>random</colorMode>\n<width>2</width>\n"));
+    }
+
+    #[test]
+    fn test_kml_label_style() {
+        let mut buf: Vec<u8> = vec![];
+
+        {
+            let mut kml = KmlFile::from_writer(&mut buf).unwrap();
+            kml.start_style(Some("hidden")).unwrap();
+            kml.create_label_style(Some("FFFFFFFF"), 0.0).unwrap();
+            kml.finish_style().unwrap();
+        }
+
+        let contents = String::from_utf8(buf).unwrap();
+        assert!(contents.contains(concat!(
+            "<LabelStyle>\n",
+            "<color>FFFFFFFF</color>\n",
+            "<colorMode>normal</colorMode>\n",
+            "<scale>0</scale>\n",
+            "</LabelStyle>\n",
+        )));
+    }
+
+    #[test]
+    fn test_kml_ground_overlay() {
+        use crate::Coord;
+
+        let bbox = BoundingBox {
+            ll: Coord {
+                lat: 44.0,
+                lon: -120.5,
+            },
+            ur: Coord {
+                lat: 46.25,
+                lon: -118.0,
+            },
+        };
+
+        let mut buf: Vec<u8> = vec![];
+
+        {
+            let mut kml = KmlFile::from_writer(&mut buf).unwrap();
+            kml.ground_overlay("fire&power.png", bbox, Some("80FFFFFF"))
+                .unwrap();
+            kml.ground_overlay("power.png", bbox, None).unwrap();
+        }
+
+        let contents = String::from_utf8(buf).unwrap();
+        assert!(contents.contains(concat!(
+            "<GroundOverlay>\n",
+            "<color>80FFFFFF</color>\n",
+            "<Icon><href>fire&amp;power.png</href></Icon>\n",
+            "<LatLonBox>\n",
+            "<north>46.25</north>\n",
+            "<south>44</south>\n",
+            "<east>-118</east>\n",
+            "<west>-120.5</west>\n",
+            "</LatLonBox>\n",
+            "</GroundOverlay>\n",
+        )));
+        assert!(contents.contains("<GroundOverlay>\n<Icon><href>power.png</href></Icon>\n"));
+    }
+
+    #[test]
+    fn test_kmz_compression_levels() {
+        let path =
+            std::env::temp_dir().join(format!("satfire_kmz_test_{}.kmz", std::process::id()));
+
+        assert!(KmzFile::with_compression(&path, -1).is_err());
+        assert!(KmzFile::with_compression(&path, 10).is_err());
+
+        for level in [0, KmzFile::DEFAULT_COMPRESSION_LEVEL] {
+            {
+                let mut kmz = KmzFile::with_compression(&path, level).unwrap();
+                kmz.start_folder(Some("Test"), None, false).unwrap();
+                kmz.finish_folder().unwrap();
+            }
+
+            let f = std::fs::File::open(&path).unwrap();
+            let mut archive = zip::ZipArchive::new(f).unwrap();
+            let mut doc = String::new();
+            std::io::Read::read_to_string(&mut archive.by_name("doc.kml").unwrap(), &mut doc)
+                .unwrap();
+            assert!(doc.contains("<name>Test</name>"));
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -2,26 +2,121 @@
 #![allow(dead_code)]
 
 // Public API
-pub use cluster::{Cluster, ClusterList};
+pub use cluster::{Cluster, ClusterList, ClusterListError, ClusterListOptions, Connectivity};
 pub use database::{
     ClusterDatabase, ClusterDatabaseAddCluster, ClusterDatabaseClusterRow,
-    ClusterDatabaseQueryClusterPresent, ClusterDatabaseQueryClusters, FiresDatabase,
-    FiresDatabaseAddFire, JointFiresClusterDatabases, JointQuerySingleFire,
+    ClusterDatabaseQueryClusterPresent, ClusterDatabaseQueryClusters, CoverageEntry, Durability,
+    FiresDatabase, FiresDatabaseAddFire, JointFiresClusterDatabases, JointQuerySingleFire,
 };
-pub use fire::{Fire, FireList, FireListUpdateResult, FireListView};
-pub use geo::{BoundingBox, Coord, Geo};
+pub use fire::{Fire, FireEvent, FireList, FireListUpdateResult, FireListView};
+pub use firesatimage::ImageBuffers;
+pub use geo::{BoundingBox, Coord, Geo, IntersectResult, Line};
 pub use kml::{KmlFile, KmlWriter, KmzFile};
-pub use pixel::{Pixel, PixelList};
+pub use pixel::{ColorMode, OutlinePolygon, Pixel, PixelColorRamp, PixelList};
 pub use satellite::{
-    parse_satellite_description_from_file_name, DataQualityFlagCode, MaskCode, Satellite, Sector,
+    parse_satellite_description_from_file_name, DataQualityFlagCode, MaskCategory, MaskCode,
+    Satellite, Sector,
 };
 
-/// A generic error type.
-pub type SatFireError = Box<dyn Error + Send + Sync>;
+/// The error type for the library.
+#[derive(Debug)]
+pub enum SatFireError {
+    /// An error reported by the netCDF library while reading a satellite file.
+    NetCdf(String),
+    /// An error reported by SQLite.
+    Database(rusqlite::Error),
+    /// An IO error.
+    Io(std::io::Error),
+    /// An error unpacking a zip archive.
+    Zip(zip::result::ZipError),
+    /// A time string could not be parsed.
+    Time(chrono::ParseError),
+    /// A value could not be parsed, e.g. a satellite name or a database column.
+    Parse(String),
+    /// Any other error.
+    Other(String),
+}
+
+impl Display for SatFireError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::NetCdf(msg) => write!(f, "netCDF error: {}", msg),
+            Self::Database(err) => write!(f, "database error: {}", err),
+            Self::Io(err) => write!(f, "IO error: {}", err),
+            Self::Zip(err) => write!(f, "zip error: {}", err),
+            Self::Time(err) => write!(f, "time parse error: {}", err),
+            Self::Parse(msg) => write!(f, "parse error: {}", msg),
+            Self::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for SatFireError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Database(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Zip(err) => Some(err),
+            Self::Time(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for SatFireError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Database(err)
+    }
+}
+
+impl From<std::io::Error> for SatFireError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for SatFireError {
+    fn from(err: zip::result::ZipError) -> Self {
+        Self::Zip(err)
+    }
+}
+
+impl From<chrono::ParseError> for SatFireError {
+    fn from(err: chrono::ParseError) -> Self {
+        Self::Time(err)
+    }
+}
+
+impl From<std::num::TryFromIntError> for SatFireError {
+    fn from(err: std::num::TryFromIntError) -> Self {
+        Self::Parse(err.to_string())
+    }
+}
+
+impl From<std::ffi::NulError> for SatFireError {
+    fn from(err: std::ffi::NulError) -> Self {
+        Self::Other(err.to_string())
+    }
+}
+
+impl From<&str> for SatFireError {
+    fn from(msg: &str) -> Self {
+        Self::Other(msg.to_owned())
+    }
+}
+
+impl From<String> for SatFireError {
+    fn from(msg: String) -> Self {
+        Self::Other(msg)
+    }
+}
 
 /// A generic result type.
 pub type SatFireResult<T> = Result<T, SatFireError>;
 
+/// A result type for the binaries, which mix errors from this library and their dependencies.
+pub type AppResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
 /// Parse the file name and find the scan start time.
 pub fn start_time_from_file_name(fname: &str) -> Option<DateTime<Utc>> {
     let start_idx = fname.find("_s")? + 2;
@@ -52,6 +147,17 @@ pub fn end_time_from_file_name(fname: &str) -> Option<DateTime<Utc>> {
         .map(|naive| DateTime::<Utc>::from_utc(naive, Utc))
 }
 
+/// Format a number for JSON output.
+///
+/// JSON has no representation for NaN or infinity, so those are written as null.
+pub fn json_number(val: f64) -> String {
+    if val.is_finite() {
+        format!("{}", val)
+    } else {
+        "null".to_owned()
+    }
+}
+
 // Private API
 mod cluster;
 mod database;
@@ -63,7 +169,10 @@ mod pixel;
 mod satellite;
 
 use chrono::{DateTime, NaiveDateTime, Utc};
-use std::error::Error;
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
 
 // test
 #[cfg(test)]
@@ -71,6 +180,15 @@ mod test {
     use super::*;
     use chrono::NaiveDate;
 
+    #[test]
+    fn test_json_number() {
+        assert_eq!(json_number(12.5), "12.5");
+        assert_eq!(json_number(-3.0), "-3");
+        assert_eq!(json_number(f64::NAN), "null");
+        assert_eq!(json_number(f64::INFINITY), "null");
+        assert_eq!(json_number(f64::NEG_INFINITY), "null");
+    }
+
     #[test]
     fn test_start_time_from_file_name() {
         const CASE1: &str =
@@ -88,4 +206,24 @@ mod test {
             DateTime::<Utc>::from_utc(NaiveDate::from_yo(2021, 213).and_hms(1, 9, 38), Utc)
         );
     }
+
+    #[test]
+    fn test_satfire_error_conversions() {
+        let err: SatFireError = "no fire here".into();
+        assert!(matches!(err, SatFireError::Other(_)));
+        assert_eq!(err.to_string(), "no fire here");
+
+        let err: SatFireError = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        assert!(matches!(err, SatFireError::Io(_)));
+        assert!(err.source().is_some());
+
+        let err: SatFireError = rusqlite::Error::QueryReturnedNoRows.into();
+        assert!(matches!(err, SatFireError::Database(_)));
+
+        let err: SatFireError = NaiveDateTime::parse_from_str("not a time", "%Y")
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, SatFireError::Time(_)));
+        assert!(err.source().is_some());
+    }
 }
@@ -1,9 +1,15 @@
 use crate::{
-    geo::{BoundingBox, Coord, Geo},
+    geo::{BoundingBox, Coord, Geo, Line},
+    json_number,
     kml::KmlWriter,
     satellite::{DataQualityFlagCode, MaskCode},
+    SatFireResult,
 };
+use once_cell::sync::OnceCell;
+use rustc_hash::FxHashMap as HashMap;
 use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
     io::{Read, Write},
     mem::size_of,
 };
@@ -11,6 +17,12 @@ use std::{
 const OVERLAP_FUDGE_FACTOR: f64 = 1.0e-2;
 
 /// The coordinates describing the area of a pixel viewed from a GOES satellite.
+///
+/// Pixels implement `PartialEq`, `Eq`, `Hash`, and `Ord` so they can be used as keys in
+/// collections. These comparisons are exact, every field must be bit-for-bit identical for two
+/// pixels to be equal. Use [Pixel::approx_equal] for a comparison that tolerates floating point
+/// differences in the coordinates.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct Pixel {
@@ -48,6 +60,68 @@ pub struct Pixel {
     pub data_quality_flag: DataQualityFlagCode,
 }
 
+impl Pixel {
+    /// All the floating point values in the pixel, in the order they are compared. The first
+    /// eight values are the corners.
+    fn float_fields(&self) -> [f64; 12] {
+        [
+            self.ul.lat,
+            self.ul.lon,
+            self.ll.lat,
+            self.ll.lon,
+            self.lr.lat,
+            self.lr.lon,
+            self.ur.lat,
+            self.ur.lon,
+            self.power,
+            self.area,
+            self.temperature,
+            self.scan_angle,
+        ]
+    }
+}
+
+impl PartialEq for Pixel {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pixel {}
+
+impl PartialOrd for Pixel {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pixel {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // total_cmp only returns Equal when the bits are identical, which matches Hash.
+        self.float_fields()
+            .iter()
+            .zip(other.float_fields().iter())
+            .map(|(a, b)| a.total_cmp(b))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+            .then(self.mask_flag.cmp(&other.mask_flag))
+            .then(self.data_quality_flag.cmp(&other.data_quality_flag))
+    }
+}
+
+impl Hash for Pixel {
+    /// Hash the bit patterns of the corner coordinates.
+    ///
+    /// Pixels that are equal always have identical corners, so this is consistent with `Eq`. Any
+    /// NaN corners make the pixel meaningless as a key since NaN values with different bit
+    /// patterns will never be equal, so callers should filter those out first.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for val in &self.float_fields()[..8] {
+            val.to_bits().hash(state);
+        }
+    }
+}
+
 impl Pixel {
     fn max_merge(&mut self, other: &Pixel) {
         self.power = self.power.max(other.power);
@@ -87,38 +161,38 @@ impl Pixel {
         Ok(())
     }
 
-    fn read_bytes<R: Read>(r: &mut R) -> Self {
+    fn read_bytes<R: Read>(r: &mut R) -> Result<Self, std::io::Error> {
         let mut buf: [u8; 8] = [0; 8];
 
-        let mut read_coord = || -> Coord {
-            let _ = r.read_exact(&mut buf);
+        let mut read_coord = || -> Result<Coord, std::io::Error> {
+            r.read_exact(&mut buf)?;
             let lat = f64::from_le_bytes(buf);
-            let _ = r.read_exact(&mut buf);
+            r.read_exact(&mut buf)?;
             let lon = f64::from_le_bytes(buf);
-            Coord { lat, lon }
+            Ok(Coord { lat, lon })
         };
 
-        let ul = read_coord();
-        let ll = read_coord();
-        let lr = read_coord();
-        let ur = read_coord();
+        let ul = read_coord()?;
+        let ll = read_coord()?;
+        let lr = read_coord()?;
+        let ur = read_coord()?;
 
-        let _ = r.read_exact(&mut buf);
+        r.read_exact(&mut buf)?;
         let power = f64::from_le_bytes(buf);
-        let _ = r.read_exact(&mut buf);
+        r.read_exact(&mut buf)?;
         let area = f64::from_le_bytes(buf);
-        let _ = r.read_exact(&mut buf);
+        r.read_exact(&mut buf)?;
         let temperature = f64::from_le_bytes(buf);
-        let _ = r.read_exact(&mut buf);
+        r.read_exact(&mut buf)?;
         let scan_angle = f64::from_le_bytes(buf);
 
         let mut buf: [u8; 2] = [0; 2];
-        let _ = r.read_exact(&mut buf);
+        r.read_exact(&mut buf)?;
         let mask_flag = MaskCode(i16::from_le_bytes(buf));
-        let _ = r.read_exact(&mut buf);
+        r.read_exact(&mut buf)?;
         let data_quality_flag = DataQualityFlagCode(i16::from_le_bytes(buf));
 
-        Pixel {
+        Ok(Pixel {
             ul,
             ll,
             lr,
@@ -129,7 +203,7 @@ impl Pixel {
             scan_angle,
             mask_flag,
             data_quality_flag,
-        }
+        })
     }
 }
 
@@ -149,7 +223,7 @@ impl Geo for Pixel {
          *  5) Find the intersection of the two resulting lines, that is the centroid of the
          *     quadrilateral.
          */
-        use crate::geo::{triangle_centroid, Line};
+        use crate::geo::triangle_centroid;
 
         let t1_c = triangle_centroid(self.ul, self.ll, self.lr);
         let t2_c = triangle_centroid(self.ul, self.ur, self.lr);
@@ -320,6 +394,105 @@ impl Pixel {
         false
     }
 
+    /// Calculate the area that these pixels have in common.
+    ///
+    /// The pixels are clipped against each other and the area of the resulting polygon is
+    /// returned in square degrees (lat-lon space). If one pixel is entirely inside the other, this
+    /// is the area of the smaller pixel. Pixels that do not overlap have an overlap area of 0.0.
+    ///
+    /// The eps parameter is used when comparing floating point values, see [Pixel::overlap].
+    pub fn overlap_area(&self, other: &Pixel, eps: f64) -> f64 {
+        use crate::geo::polygon_signed_area;
+
+        match self.intersection(other, eps) {
+            Some(vertices) => polygon_signed_area(&vertices).abs(),
+            None => 0.0,
+        }
+    }
+
+    /// Calculate the overlapping area as a fraction of the smaller pixel's area.
+    ///
+    /// The result is in the range 0.0 to 1.0, where 0.0 means the pixels do not overlap and 1.0
+    /// means the smaller pixel is entirely inside the larger one. Pixels that are `approx_equal`
+    /// will have an overlap fraction close to 1.0.
+    ///
+    /// The eps parameter is used when comparing floating point values, see [Pixel::overlap].
+    pub fn overlap_fraction(&self, other: &Pixel, eps: f64) -> f64 {
+        use crate::geo::polygon_signed_area;
+
+        let overlap_area = self.overlap_area(other, eps);
+        if overlap_area <= 0.0 {
+            return 0.0;
+        }
+
+        let self_area = polygon_signed_area(&self.corners()).abs();
+        let other_area = polygon_signed_area(&other.corners()).abs();
+
+        (overlap_area / self_area.min(other_area)).clamp(0.0, 1.0)
+    }
+
+    /// Get the polygon formed by the intersection of these pixels.
+    ///
+    /// The vertices are returned in clockwise order and the first vertex is not repeated at the
+    /// end. If the pixels do not overlap, or the overlap is degenerate (e.g. a shared edge or
+    /// corner with no area), then `None` is returned.
+    ///
+    /// The eps parameter is used when comparing floating point values, see [Pixel::overlap].
+    pub fn intersection(&self, other: &Pixel, eps: f64) -> Option<Vec<Coord>> {
+        use crate::geo::{clip_convex_polygon, polygon_signed_area};
+
+        if !self.bounding_box().overlap(&other.bounding_box(), eps) {
+            return None;
+        }
+
+        let mut clipped = clip_convex_polygon(&self.corners(), &other.corners(), eps);
+        if clipped.len() < 3 {
+            return None;
+        }
+
+        // A sliver no wider than eps is a shared edge, not an overlap. The width of the polygon is
+        // about its area divided by its length, so compare the area to eps times the length.
+        let signed_area = polygon_signed_area(&clipped);
+        let length = clipped
+            .iter()
+            .flat_map(|a| {
+                clipped
+                    .iter()
+                    .map(move |b| (a.lat - b.lat).hypot(a.lon - b.lon))
+            })
+            .fold(0.0, f64::max);
+        if signed_area.abs() <= eps * length {
+            return None;
+        }
+
+        // Counter-clockwise polygons have a positive area.
+        if signed_area > 0.0 {
+            clipped.reverse();
+        }
+
+        Some(clipped)
+    }
+
+    /// Calculate the area of the pixel footprint on the surface of the Earth in square meters.
+    ///
+    /// Unlike the `area` field, which is the area of the fire reported by the satellite, this is
+    /// the geometric area of the pixel itself. The quadrilateral is split into two triangles along
+    /// the ul-lr diagonal, as in the centroid calculation, and the area of each triangle is found
+    /// on a sphere with the mean radius of the Earth (6,371,008.8 m).
+    pub fn geographic_area_m2(&self) -> f64 {
+        use crate::geo::{spherical_triangle_area, EARTH_RADIUS_M};
+
+        let t1 = spherical_triangle_area(self.ul, self.ll, self.lr);
+        let t2 = spherical_triangle_area(self.ul, self.ur, self.lr);
+
+        (t1 + t2) * EARTH_RADIUS_M * EARTH_RADIUS_M
+    }
+
+    /// Get the corners of the pixel in counter-clockwise order starting with the upper left.
+    fn corners(&self) -> [Coord; 4] {
+        [self.ul, self.ll, self.lr, self.ur]
+    }
+
     /// Determine if satellite pixels are adjacent.
     ///
     /// Adjacent is defined as having at least one corner that is `eps` close to a coordinate in the
@@ -344,22 +517,25 @@ impl Pixel {
         let self_coords = [self.ul, self.ur, self.lr, self.ll];
         let other_coords = [other.ul, other.ur, other.lr, other.ll];
 
-        // Count the number of close coords and mark which ones are close.
+        // Mark which coords are close to a coord in the other pixel.
         let mut self_close = [false, false, false, false];
         let mut other_close = [false, false, false, false];
-        let mut num_close_coords = 0;
         for i in 0..self_coords.len() {
             for j in 0..other_coords.len() {
                 if self_coords[i].is_close(other_coords[j], eps) {
-                    num_close_coords += 1;
                     self_close[i] = true;
                     other_close[j] = true;
                 }
             }
         }
 
+        // Count distinct corners, since with a large eps one corner may be close to more than one
+        // corner of the other pixel.
+        let num_self_close = self_close.iter().filter(|&&close| close).count();
+        let num_other_close = other_close.iter().filter(|&&close| close).count();
+
         // bail out early if we can
-        if !(1..=2).contains(&num_close_coords) {
+        if !(1..=2).contains(&num_self_close) || !(1..=2).contains(&num_other_close) {
             return false;
         }
 
@@ -436,30 +612,43 @@ impl Pixel {
 }
 
 /// A pixel list stores a list of Pixel objects.
+///
+/// The bounding box of the list is cached the first time it is needed, and cleared any time the
+/// list is modified.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 #[derive(Debug, Clone)]
-pub struct PixelList(Vec<Pixel>);
+pub struct PixelList(
+    Vec<Pixel>,
+    #[cfg_attr(feature = "serde", serde(skip))] OnceCell<BoundingBox>,
+);
 
 impl Geo for PixelList {
+    /// The average of the centroids of the pixels.
+    ///
+    /// The coordinates are NaN for an empty list, use [PixelList::centroid_opt] to check for that.
     fn centroid(&self) -> Coord {
-        let mut centroid = Coord { lat: 0.0, lon: 0.0 };
-        for pixel in &self.0 {
-            let coord = pixel.centroid();
-            centroid.lat += coord.lat;
-            centroid.lon += coord.lon;
-        }
+        let sum = self
+            .0
+            .iter()
+            .map(|pixel| pixel.centroid())
+            .fold(Coord { lat: 0.0, lon: 0.0 }, |acc, coord| acc + coord);
 
-        centroid.lat /= self.0.len() as f64;
-        centroid.lon /= self.0.len() as f64;
+        sum / self.0.len() as f64
+    }
 
-        centroid
+    fn bounding_box(&self) -> BoundingBox {
+        *self.1.get_or_init(|| self.calculate_bounding_box())
     }
+}
 
+impl PixelList {
     #[rustfmt::skip]
-    fn bounding_box(&self) -> BoundingBox {
-        let mut min_lat = std::f64::INFINITY;
-        let mut max_lat = -std::f64::INFINITY;
-        let mut min_lon = std::f64::INFINITY;
-        let mut max_lon = -std::f64::INFINITY;
+    fn calculate_bounding_box(&self) -> BoundingBox {
+        let mut min_lat = f64::INFINITY;
+        let mut max_lat = -f64::INFINITY;
+        let mut min_lon = f64::INFINITY;
+        let mut max_lon = -f64::INFINITY;
 
         for pixel in &self.0 {
             min_lat = min_lat.min(pixel.ll.lat).min(pixel.lr.lat);
@@ -478,15 +667,63 @@ impl Default for PixelList {
     }
 }
 
+impl IntoIterator for PixelList {
+    type Item = Pixel;
+    type IntoIter = std::vec::IntoIter<Pixel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PixelList {
+    type Item = &'a Pixel;
+    type IntoIter = std::slice::Iter<'a, Pixel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Pixel> for PixelList {
+    fn from_iter<I: IntoIterator<Item = Pixel>>(iter: I) -> Self {
+        PixelList(iter.into_iter().collect(), OnceCell::new())
+    }
+}
+
+impl Extend<Pixel> for PixelList {
+    fn extend<I: IntoIterator<Item = Pixel>>(&mut self, iter: I) {
+        self.invalidate_bounding_box();
+        self.0.extend(iter)
+    }
+}
+
+impl std::ops::Index<usize> for PixelList {
+    type Output = Pixel;
+
+    fn index(&self, index: usize) -> &Pixel {
+        &self.0[index]
+    }
+}
+
 impl PixelList {
     /// Create a new PixelList
     pub fn new() -> Self {
-        PixelList(vec![])
+        PixelList(vec![], OnceCell::new())
     }
 
     /// Create a new PixelList with a given capacity.
     pub fn with_capacity(capacity: usize) -> Self {
-        PixelList(Vec::with_capacity(capacity))
+        PixelList(Vec::with_capacity(capacity), OnceCell::new())
+    }
+
+    /// Get the centroid of the list, or `None` if the list is empty.
+    pub fn centroid_opt(&self) -> Option<Coord> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.centroid())
+        }
     }
 
     /// Get the number of pixels in this list.
@@ -501,14 +738,21 @@ impl PixelList {
 
     /// Append a [Pixel] to the end of the list.
     pub fn push(&mut self, pixel: Pixel) {
+        self.invalidate_bounding_box();
         self.0.push(pixel)
     }
 
     /// Empty the list, but keep it intact for reuse.
     pub fn clear(&mut self) {
+        self.invalidate_bounding_box();
         self.0.clear()
     }
 
+    /// Clear the cached bounding box, this must be called any time the pixels are modified.
+    fn invalidate_bounding_box(&mut self) {
+        self.1.take();
+    }
+
     /// Calculate the total power in a PixelList, megawatts.
     pub fn total_power(&self) -> f64 {
         self.0
@@ -519,7 +763,7 @@ impl PixelList {
     }
 
     /// Calculate the total fire area in a PixelList, square meters.
-    pub fn total_are(&self) -> f64 {
+    pub fn total_area(&self) -> f64 {
         self.0
             .iter()
             .filter(|p| !p.area.is_infinite() && !p.area.is_nan())
@@ -527,33 +771,82 @@ impl PixelList {
             .sum()
     }
 
+    /// Calculate the total fire area in a PixelList, square meters.
+    #[deprecated(note = "misspelled, use total_area instead")]
+    pub fn total_are(&self) -> f64 {
+        self.total_area()
+    }
+
     /// Calculate the maximum fire temperature in a PixelList, kelvin.
+    ///
+    /// Returns negative infinity if there are no pixels with a finite temperature.
+    #[deprecated(note = "returns -inf for empty lists, use max_temperature_opt instead")]
     pub fn maximum_temperature(&self) -> f64 {
-        self.0
-            .iter()
-            .filter(|p| !p.temperature.is_infinite() && !p.temperature.is_nan())
-            .map(|p| p.temperature)
-            .fold(-std::f64::INFINITY, |acc, t| acc.max(t))
+        self.max_temperature_opt().unwrap_or(-f64::INFINITY)
+    }
+
+    /// Calculate the maximum fire temperature in a PixelList, kelvin.
+    ///
+    /// Returns `None` if there are no pixels with a finite temperature.
+    pub fn max_temperature_opt(&self) -> Option<f64> {
+        Self::max_finite(self.0.iter().map(|p| p.temperature))
     }
 
     /// Calculate the maximum scan angle in a PixelList, degrees.
+    ///
+    /// Returns negative infinity if there are no pixels with a finite scan angle.
+    #[deprecated(note = "returns -inf for empty lists, use max_scan_angle_opt instead")]
     pub fn maximum_scan_angle(&self) -> f64 {
-        self.0
-            .iter()
-            .filter(|p| !p.scan_angle.is_infinite() && !p.scan_angle.is_nan())
-            .map(|p| p.scan_angle)
-            .fold(-std::f64::INFINITY, |acc, t| acc.max(t))
+        self.max_scan_angle_opt().unwrap_or(-f64::INFINITY)
+    }
+
+    /// Calculate the maximum scan angle in a PixelList, degrees.
+    ///
+    /// Returns `None` if there are no pixels with a finite scan angle.
+    pub fn max_scan_angle_opt(&self) -> Option<f64> {
+        Self::max_finite(self.0.iter().map(|p| p.scan_angle))
+    }
+
+    /// Find the maximum of the finite values, if there are any.
+    fn max_finite<I: Iterator<Item = f64>>(vals: I) -> Option<f64> {
+        vals.filter(|v| v.is_finite()).reduce(f64::max)
     }
 
     /// Check to see if these two PixelList objects are adjacent or overlapping.
     pub fn adjacent_to_or_overlaps(&self, other: &PixelList, eps: f64) -> bool {
+        // Below this many pairs of pixels, building a spatial index isn't worth it.
+        const BRUTE_FORCE_MAX_PAIRS: usize = 1_024;
+
         if !self.bounding_box().overlap(&other.bounding_box(), eps) {
             return false;
         }
 
+        if self.len() * other.len() <= BRUTE_FORCE_MAX_PAIRS {
+            return self.adjacent_to_or_overlaps_brute_force(other, eps);
+        }
+
+        // Index the larger list and query it with each pixel of the smaller one.
+        if self.len() >= other.len() {
+            Self::any_indexed_pair(&self.0, &other.0, eps, |o_pixel, s_pixel| {
+                s_pixel.is_adjacent_to_or_overlaps(o_pixel, eps)
+            })
+        } else {
+            Self::any_indexed_pair(&other.0, &self.0, eps, |s_pixel, o_pixel| {
+                s_pixel.is_adjacent_to_or_overlaps(o_pixel, eps)
+            })
+        }
+    }
+
+    /// Check every pair of pixels, this is the reference for [PixelList::adjacent_to_or_overlaps].
+    fn adjacent_to_or_overlaps_brute_force(&self, other: &PixelList, eps: f64) -> bool {
+        // Compute the bounding boxes of the other pixels once instead of for every pair.
+        let other_boxes: Vec<BoundingBox> = other.0.iter().map(|p| p.bounding_box()).collect();
+
         for s_pixel in &self.0 {
-            for o_pixel in &other.0 {
-                if s_pixel.is_adjacent_to_or_overlaps(o_pixel, eps) {
+            let s_box = s_pixel.bounding_box();
+
+            for (o_pixel, o_box) in other.0.iter().zip(&other_boxes) {
+                if s_box.overlap(o_box, eps) && s_pixel.is_adjacent_to_or_overlaps(o_pixel, eps) {
                     return true;
                 }
             }
@@ -562,17 +855,154 @@ impl PixelList {
         false
     }
 
+    /// Build an R-tree over the indexed pixels and check if test returns true for any pair of a
+    /// query pixel and an indexed pixel with overlapping bounding boxes.
+    ///
+    /// The arguments to test are the query pixel followed by the indexed pixel.
+    fn any_indexed_pair<F>(indexed: &[Pixel], queries: &[Pixel], eps: f64, test: F) -> bool
+    where
+        F: Fn(&Pixel, &Pixel) -> bool + Copy,
+    {
+        use crate::geo::Hilbert2DRTreeView;
+        use std::ops::ControlFlow;
+
+        let mut indexed = indexed.to_vec();
+        let mut view = match Hilbert2DRTreeView::build_for(&mut indexed, None) {
+            Some(view) => view,
+            None => return false,
+        };
+
+        queries.iter().any(|query| {
+            let region = query.bounding_box().expanded_by(eps, eps);
+
+            view.foreach(region, false, |candidate, _, _| {
+                if test(query, candidate) {
+                    (false, ControlFlow::Break(true))
+                } else {
+                    (false, ControlFlow::Continue(false))
+                }
+            })
+        })
+    }
+
+    /// Check if a coordinate is inside any of the pixels in the list.
+    ///
+    /// The eps parameter is used when comparing floating point values, see
+    /// [Pixel::contains_coord].
+    pub fn contains_coord(&self, coord: Coord, eps: f64) -> bool {
+        if !self.bounding_box().contains_coord(coord, eps) {
+            return false;
+        }
+
+        self.0.iter().any(|pixel| pixel.contains_coord(coord, eps))
+    }
+
+    /// Find the pixel with the centroid closest to `coord`.
+    ///
+    /// Returns the pixel and the great circle distance from its centroid to `coord` in meters, or
+    /// `None` if the list is empty.
+    pub fn nearest_pixel(&self, coord: Coord) -> Option<(&Pixel, f64)> {
+        self.0
+            .iter()
+            .map(|pixel| (pixel, pixel.centroid().distance_haversine_m(coord)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
     pub fn pixels(&self) -> &[Pixel] {
         &self.0
     }
 
-    pub fn max_merge(&mut self, other: &PixelList) {
+    /// Get a reference to the pixel at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&Pixel> {
+        self.0.get(index)
+    }
+
+    /// Keep only the pixels for which the predicate returns true.
+    pub fn retain<F: FnMut(&Pixel) -> bool>(&mut self, f: F) {
+        self.invalidate_bounding_box();
+        self.0.retain(f)
+    }
+
+    /// Collapse pixels that overlap each other into a single pixel.
+    ///
+    /// This is useful when combining pixels from different scan sectors where the same ground
+    /// pixel may show up more than once with slightly different corners. Pixels that are merely
+    /// adjacent, but do not overlap, are left alone. Overlapping pixels are combined with the same
+    /// rules as [PixelList::max_merge], keeping the corners of the first pixel found.
+    ///
+    /// The pixels are sorted by the southern edge of their bounding box so that only pixels in
+    /// nearby rows need to be compared, so the original order of the list is not preserved.
+    pub fn dedup_overlapping(&mut self, eps: f64) {
+        self.invalidate_bounding_box();
+        let mut pixels = std::mem::take(&mut self.0);
+        pixels.sort_by(|a, b| a.bounding_box().ll.lat.total_cmp(&b.bounding_box().ll.lat));
+
+        let mut kept: Vec<Pixel> = Vec::with_capacity(pixels.len());
+
+        // Indexes into kept for pixels that might still overlap with the remaining pixels.
+        let mut active: Vec<usize> = vec![];
+
+        for pixel in pixels {
+            let min_lat = pixel.bounding_box().ll.lat;
+            active.retain(|&i| kept[i].bounding_box().ur.lat >= min_lat - eps);
+
+            let duplicate = active
+                .iter()
+                .copied()
+                .find(|&i| kept[i].overlap(&pixel, eps) && !kept[i].is_adjacent_to(&pixel, eps));
+
+            match duplicate {
+                Some(i) => kept[i].max_merge(&pixel),
+                None => {
+                    active.push(kept.len());
+                    kept.push(pixel);
+                }
+            }
+        }
+
+        self.0 = kept;
+    }
+
+    /// Sort the pixels from highest to lowest power, with NaN or infinite values at the end.
+    pub fn sort_by_power_desc(&mut self) {
+        self.0
+            .sort_by(|a, b| Self::cmp_finite_desc(a.power, b.power));
+    }
+
+    /// Sort the pixels from highest to lowest temperature, with NaN or infinite values at the end.
+    pub fn sort_by_temperature_desc(&mut self) {
+        self.0
+            .sort_by(|a, b| Self::cmp_finite_desc(a.temperature, b.temperature));
+    }
+
+    /// Order finite values from largest to smallest and place all non-finite values after them.
+    fn cmp_finite_desc(a: f64, b: f64) -> Ordering {
+        match (a.is_finite(), b.is_finite()) {
+            (true, true) => b.total_cmp(&a),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => Ordering::Equal,
+        }
+    }
+
+    /// Merge the pixels from another list into this one.
+    ///
+    /// Pixels in `other` that are approximately equal to a pixel in this list, with `eps` as the
+    /// tolerance, are combined into that pixel using the `combine` function. Any other pixels are
+    /// added to the list.
+    pub fn merge_with<F: Fn(&mut Pixel, &Pixel)>(
+        &mut self,
+        other: &PixelList,
+        eps: f64,
+        combine: F,
+    ) {
+        self.invalidate_bounding_box();
         for other_pixel in other.0.iter() {
             let mut is_new = true;
 
             for pixel in self.0.iter_mut() {
-                if pixel.approx_equal(other_pixel, OVERLAP_FUDGE_FACTOR) {
-                    pixel.max_merge(other_pixel);
+                if pixel.approx_equal(other_pixel, eps) {
+                    combine(pixel, other_pixel);
                     is_new = false;
                     break;
                 }
@@ -583,19 +1013,302 @@ impl PixelList {
             }
         }
     }
+
+    /// Merge the pixels from another list into this one, keeping the maximum power, area, and
+    /// temperature and the minimum mask and data quality flag codes of matching pixels.
+    pub fn max_merge(&mut self, other: &PixelList) {
+        self.merge_with(other, OVERLAP_FUDGE_FACTOR, Pixel::max_merge)
+    }
+
+    /// Get the outline of the area covered by all the pixels in this list.
+    ///
+    /// Pixels are grouped together using [Pixel::is_adjacent_to_or_overlaps], and each group is
+    /// dissolved into polygons by removing the edges shared between neighboring pixels. This works
+    /// best for pixels from the same satellite scan, since neighboring pixels share corners.
+    /// Vertices that lie along a straight edge are removed.
+    ///
+    /// Each polygon has an outer ring and the holes inside it, see [OutlinePolygon].
+    pub fn dissolve_outline(&self, eps: f64) -> Vec<OutlinePolygon> {
+        use crate::geo::{polygon_contains_coord, polygon_signed_area};
+
+        let mut polygons = vec![];
+
+        for group in self.adjacent_groups(eps) {
+            let edges = Self::unshared_edges(&group, eps);
+
+            // The outer boundary goes counter-clockwise, holes go clockwise.
+            let (outers, holes): (Vec<_>, Vec<_>) = Self::chain_edges(edges, eps)
+                .into_iter()
+                .filter(|ring| ring.len() >= 3 && polygon_signed_area(ring) != 0.0)
+                .partition(|ring| polygon_signed_area(ring) > 0.0);
+
+            let first_polygon = polygons.len();
+            polygons.extend(outers.into_iter().map(|mut outer| {
+                outer.reverse();
+                OutlinePolygon {
+                    outer,
+                    holes: vec![],
+                }
+            }));
+
+            for mut hole in holes {
+                // The pixels are to the left of a hole edge, so a point just to the left of the
+                // middle of an edge is inside a pixel and away from any other edges.
+                let (start, end) = (hole[0], hole[1]);
+                let middle = (start + end) / 2.0;
+                let left = Coord {
+                    lat: end.lon - start.lon,
+                    lon: start.lat - end.lat,
+                };
+                let test_coord = middle + left * 1.0e-3;
+
+                if let Some(polygon) = polygons[first_polygon..]
+                    .iter_mut()
+                    .find(|polygon| polygon_contains_coord(&polygon.outer, test_coord))
+                {
+                    hole.reverse();
+                    polygon.holes.push(hole);
+                }
+            }
+        }
+
+        polygons
+    }
+
+    /// Partition the pixels into groups that are adjacent to or overlap each other.
+    fn adjacent_groups(&self, eps: f64) -> Vec<Vec<&Pixel>> {
+        // Pixels that touch have the lower left corners of their bounding boxes within one pixel
+        // size of each other, so only the neighboring grid cells need to be checked.
+        let boxes: Vec<BoundingBox> = self.0.iter().map(|pixel| pixel.bounding_box()).collect();
+        let max_size = boxes
+            .iter()
+            .map(|bbox| (bbox.ur.lat - bbox.ll.lat).max(bbox.ur.lon - bbox.ll.lon))
+            .fold(0.0, f64::max);
+
+        let mut grid = CoordGrid::new(max_size + eps);
+        for (i, bbox) in boxes.iter().enumerate() {
+            grid.insert(bbox.ll, i);
+        }
+
+        let mut assigned = vec![false; self.0.len()];
+        let mut groups = vec![];
+
+        for start in 0..self.0.len() {
+            if assigned[start] {
+                continue;
+            }
+
+            assigned[start] = true;
+            let mut group = vec![start];
+            let mut next = 0;
+
+            while next < group.len() {
+                let current = group[next];
+                next += 1;
+
+                for i in grid.near(boxes[current].ll) {
+                    if !assigned[i] && self.0[current].is_adjacent_to_or_overlaps(&self.0[i], eps) {
+                        assigned[i] = true;
+                        group.push(i);
+                    }
+                }
+            }
+
+            groups.push(group.into_iter().map(|i| &self.0[i]).collect());
+        }
+
+        groups
+    }
+
+    /// Get all the edges of the pixels that are not shared with another pixel in the group.
+    ///
+    /// Edges are oriented counter-clockwise around their pixel, so an edge shared by two
+    /// neighboring pixels shows up once in each direction.
+    fn unshared_edges(group: &[&Pixel], eps: f64) -> Vec<Line> {
+        let mut edges: Vec<Line> = Vec::with_capacity(group.len() * 4);
+        let mut starts = CoordGrid::new(eps);
+        for pixel in group {
+            let corners = pixel.corners();
+            for i in 0..corners.len() {
+                let edge = Line {
+                    start: corners[i],
+                    end: corners[(i + 1) % corners.len()],
+                };
+
+                // Skip duplicates from pixels that were listed more than once.
+                let is_duplicate = starts.near(edge.start).any(|j| {
+                    edges[j].start.is_close(edge.start, eps) && edges[j].end.is_close(edge.end, eps)
+                });
+
+                if !is_duplicate {
+                    starts.insert(edge.start, edges.len());
+                    edges.push(edge);
+                }
+            }
+        }
+
+        // An edge is shared if another edge runs the other way between the same corners.
+        let shared: Vec<bool> = edges
+            .iter()
+            .map(|edge| {
+                starts.near(edge.end).any(|j| {
+                    edges[j].start.is_close(edge.end, eps) && edges[j].end.is_close(edge.start, eps)
+                })
+            })
+            .collect();
+
+        edges
+            .into_iter()
+            .zip(shared)
+            .filter(|(_, shared)| !shared)
+            .map(|(edge, _)| edge)
+            .collect()
+    }
+
+    /// Link edges end to start to form closed rings, dropping any chains that don't close.
+    fn chain_edges(edges: Vec<Line>, eps: f64) -> Vec<Vec<Coord>> {
+        let mut starts = CoordGrid::new(eps);
+        for (i, edge) in edges.iter().enumerate() {
+            starts.insert(edge.start, i);
+        }
+
+        let mut used = vec![false; edges.len()];
+        let mut rings = vec![];
+
+        for first in 0..edges.len() {
+            if used[first] {
+                continue;
+            }
+            used[first] = true;
+
+            let ring_start = edges[first].start;
+            let mut current = edges[first].end;
+            let mut ring = vec![ring_start];
+            let mut closed = false;
+
+            while let Some(next) = starts
+                .near(current)
+                .filter(|&i| !used[i] && edges[i].start.is_close(current, eps))
+                .min()
+            {
+                used[next] = true;
+                ring.push(current);
+                current = edges[next].end;
+
+                if current.is_close(ring_start, eps) {
+                    closed = true;
+                    break;
+                }
+            }
+
+            if closed {
+                rings.push(Self::remove_colinear_vertices(ring, eps));
+            }
+        }
+
+        rings
+    }
+
+    /// Remove vertices that lie on a straight line between their neighbors.
+    fn remove_colinear_vertices(mut ring: Vec<Coord>, eps: f64) -> Vec<Coord> {
+        let mut i = 0;
+        while ring.len() > 3 && i < ring.len() {
+            let prev = ring[(i + ring.len() - 1) % ring.len()];
+            let next = ring[(i + 1) % ring.len()];
+
+            let line = Line {
+                start: prev,
+                end: next,
+            };
+
+            if line.is_close(ring[i], eps) {
+                ring.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        ring
+    }
+}
+
+/// A polygon from the outline of a [PixelList], see [PixelList::dissolve_outline].
+///
+/// None of the rings repeat the first vertex at the end.
+#[derive(Debug, Clone)]
+pub struct OutlinePolygon {
+    /// The outer boundary in clockwise order.
+    pub outer: Vec<Coord>,
+    /// The boundaries of any holes inside the outer boundary in counter-clockwise order.
+    pub holes: Vec<Vec<Coord>>,
+}
+
+/// Buckets values by the coordinate they are located at, so the values near a coordinate can be
+/// found without checking all of them.
+///
+/// The grid cells are square, so any coordinate within one cell size of the search coordinate is
+/// in the same cell or one of the eight cells around it.
+struct CoordGrid {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl CoordGrid {
+    fn new(cell_size: f64) -> Self {
+        CoordGrid {
+            cell_size: cell_size.max(f64::MIN_POSITIVE),
+            cells: HashMap::default(),
+        }
+    }
+
+    fn cell(&self, coord: Coord) -> (i64, i64) {
+        (
+            (coord.lat / self.cell_size).floor() as i64,
+            (coord.lon / self.cell_size).floor() as i64,
+        )
+    }
+
+    fn insert(&mut self, coord: Coord, value: usize) {
+        let cell = self.cell(coord);
+        self.cells.entry(cell).or_default().push(value);
+    }
+
+    /// Get the values in the cell with this coordinate and the eight cells around it.
+    fn near(&self, coord: Coord) -> impl Iterator<Item = usize> + '_ {
+        let (row, col) = self.cell(coord);
+
+        (row.saturating_sub(1)..=row.saturating_add(1))
+            .flat_map(move |r| (col.saturating_sub(1)..=col.saturating_add(1)).map(move |c| (r, c)))
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
 }
 
 /*-------------------------------------------------------------------------------------------------
  *                                         Binary Format
  *-----------------------------------------------------------------------------------------------*/
 impl PixelList {
+    /// Magic bytes at the start of a serialized PixelList.
+    const BINARY_MAGIC: [u8; 4] = *b"SFPL";
+
+    /// The current version of the binary format.
+    ///
+    /// Version 0 had no header and started with the number of pixels as a native `usize`. Version
+    /// 1 starts with the magic bytes, followed by the version as a `u16`, and then the number of
+    /// pixels as a `u64`. All values are little endian.
+    const BINARY_VERSION: u16 = 1;
+
     /// Encode the PixelList into a binary format suitable for storing in a database.
     pub fn binary_serialize(&self) -> Vec<u8> {
         // Ignore write errors since we're writing to a Vec<u8>
 
-        let mut output = Vec::with_capacity(size_of::<usize>() + size_of::<Pixel>() * self.0.len());
+        let header_size = Self::BINARY_MAGIC.len() + size_of::<u16>() + size_of::<u64>();
+        let mut output = Vec::with_capacity(header_size + size_of::<Pixel>() * self.0.len());
 
-        let _ = output.write_all(&self.0.len().to_le_bytes());
+        let _ = output.write_all(&Self::BINARY_MAGIC);
+        let _ = output.write_all(&Self::BINARY_VERSION.to_le_bytes());
+        let _ = output.write_all(&(self.0.len() as u64).to_le_bytes());
         for pixel in &self.0 {
             let _ = pixel.write_bytes(&mut output);
         }
@@ -605,19 +1318,55 @@ impl PixelList {
 
     /// Deserialize an array of bytes into a PixelList.
     ///
-    pub fn binary_deserialize<R: Read>(r: &mut R) -> Self {
-        let mut buf: [u8; size_of::<usize>()] = [0; size_of::<usize>()];
+    /// Data without the header is read as version 0 so older databases can still be used.
+    /// Returns an error if the version is unknown or if the data ends before all of the pixels in
+    /// the declared length of the list have been read.
+    pub fn binary_deserialize<R: Read>(r: &mut R) -> SatFireResult<Self> {
+        // Limit pre-allocation so a corrupt length doesn't try to allocate a huge buffer.
+        const MAX_PREALLOCATE: usize = 4_096;
+
+        let len = Self::read_binary_header(r)?;
+
+        let mut data: Vec<Pixel> = Vec::with_capacity(len.min(MAX_PREALLOCATE));
+
+        for i in 0..len {
+            let pixel = Pixel::read_bytes(r).map_err(|err| {
+                format!(
+                    "pixel list declared {} pixels but failed reading pixel {}: {}",
+                    len, i, err
+                )
+            })?;
+            data.push(pixel);
+        }
+
+        Ok(PixelList(data, OnceCell::new()))
+    }
+
+    /// Read the header and return the number of pixels in the list.
+    fn read_binary_header<R: Read>(r: &mut R) -> SatFireResult<usize> {
+        let header_err = |err| format!("error reading pixel list header: {}", err);
+
+        let mut buf: [u8; 8] = [0; 8];
+        r.read_exact(&mut buf[..4]).map_err(header_err)?;
 
-        let _ = r.read_exact(&mut buf);
-        let len = usize::from_le_bytes(buf);
+        if buf[..4] != Self::BINARY_MAGIC {
+            // Version 0, no header. The bytes already read are the start of the length.
+            let mut len_buf: [u8; size_of::<usize>()] = [0; size_of::<usize>()];
+            len_buf[..4].copy_from_slice(&buf[..4]);
+            r.read_exact(&mut len_buf[4..]).map_err(header_err)?;
 
-        let mut data: Vec<Pixel> = Vec::with_capacity(len);
+            return Ok(usize::from_le_bytes(len_buf));
+        }
 
-        for _ in 0..len {
-            data.push(Pixel::read_bytes(r));
+        let mut version: [u8; 2] = [0; 2];
+        r.read_exact(&mut version).map_err(header_err)?;
+        let version = u16::from_le_bytes(version);
+        if version != Self::BINARY_VERSION {
+            return Err(format!("unsupported pixel list binary version {}", version).into());
         }
 
-        PixelList(data)
+        r.read_exact(&mut buf).map_err(header_err)?;
+        Ok(usize::try_from(u64::from_le_bytes(buf))?)
     }
 }
 
@@ -625,30 +1374,153 @@ impl PixelList {
  *                                         KML Export
  *-----------------------------------------------------------------------------------------------*/
 
-impl PixelList {
-    fn kml_write_pixel_style<K: KmlWriter>(kml: &mut K, mut power: f64) {
-        const MAX_POWER: f64 = 3_000.0;
-        const MAX_GREEN_FOR_ORANGE: f64 = 0.647;
-        const FULL_RED_POWER: f64 = MAX_POWER / 2.0;
+/// The pixel value used to choose the color of each pixel when writing KML.
+///
+/// Each mode has a default [PixelColorRamp] for the range of values it usually sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Color by the fire radiative power, from 0 to 3,000 MW.
+    #[default]
+    Power,
+    /// Color by the fire temperature, from 400 to 1,600 K.
+    Temperature,
+    /// Color by the fire area, from 0 to 1,000,000 m^2 (1 km^2).
+    Area,
+}
 
-        let rd = 1.0;
-        let gd;
-        let mut bd = 0.0;
-        let ad = 0.6;
+impl ColorMode {
+    /// The default color ramp for this mode.
+    pub fn default_ramp(self) -> PixelColorRamp {
+        match self {
+            ColorMode::Power => PixelColorRamp::default(),
+            ColorMode::Temperature => PixelColorRamp {
+                min_value: 400.0,
+                full_red_value: 1_000.0,
+                max_value: 1_600.0,
+                ..PixelColorRamp::default()
+            },
+            ColorMode::Area => PixelColorRamp {
+                min_value: 0.0,
+                full_red_value: 500_000.0,
+                max_value: 1_000_000.0,
+                ..PixelColorRamp::default()
+            },
+        }
+    }
+
+    /// Get the value from the pixel that this mode colors by.
+    fn value(self, pixel: &Pixel) -> f64 {
+        match self {
+            ColorMode::Power => pixel.power,
+            ColorMode::Temperature => pixel.temperature,
+            ColorMode::Area => pixel.area,
+        }
+    }
+}
+
+/// Describes how pixel values map to colors when writing KML.
+///
+/// Pixels at `min_value` are orange, they get more red until they are fully red at
+/// `full_red_value`, and then they fade to white at `max_value`. Values outside of the range are
+/// clamped to it, and values that aren't numbers are treated as `max_value`.
+///
+/// The default is the power ramp, 0 to 3,000 MW with full red at 1,500 MW.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelColorRamp {
+    /// The value where pixels are orange.
+    min_value: f64,
+    /// The value where pixels are fully red.
+    full_red_value: f64,
+    /// The value where pixels are white.
+    max_value: f64,
+    /// The amount of green, from 0 to 1, at `min_value` that makes the pixel orange.
+    max_green_for_orange: f64,
+}
+
+impl Default for PixelColorRamp {
+    fn default() -> Self {
+        PixelColorRamp {
+            min_value: 0.0,
+            full_red_value: 1_500.0,
+            max_value: 3_000.0,
+            max_green_for_orange: 0.647,
+        }
+    }
+}
+
+impl PixelColorRamp {
+    /// Create a ramp that is orange at `min_value`, fully red at `full_red_value`, and white at
+    /// `max_value`.
+    ///
+    /// The values must all be finite and `min_value < full_red_value < max_value`, otherwise an
+    /// error is returned.
+    pub fn new(min_value: f64, full_red_value: f64, max_value: f64) -> SatFireResult<Self> {
+        let all_finite =
+            min_value.is_finite() && full_red_value.is_finite() && max_value.is_finite();
+        if !all_finite || min_value >= full_red_value || full_red_value >= max_value {
+            return Err(format!(
+                concat!(
+                    "invalid color ramp, values must be finite with min < full red < max:",
+                    " min={} full_red={} max={}"
+                ),
+                min_value, full_red_value, max_value
+            )
+            .into());
+        }
+
+        Ok(PixelColorRamp {
+            min_value,
+            full_red_value,
+            max_value,
+            ..PixelColorRamp::default()
+        })
+    }
+
+    /// Set the amount of green, from 0 to 1, at the minimum value that makes the pixel orange.
+    ///
+    /// An error is returned if `max_green_for_orange` is not in the range 0 to 1.
+    pub fn with_max_green_for_orange(mut self, max_green_for_orange: f64) -> SatFireResult<Self> {
+        if !(0.0..=1.0).contains(&max_green_for_orange) {
+            return Err(format!(
+                "invalid color ramp, max green for orange must be 0 to 1: {}",
+                max_green_for_orange
+            )
+            .into());
+        }
+
+        self.max_green_for_orange = max_green_for_orange;
+        Ok(self)
+    }
 
-        if power.is_infinite() {
-            power = MAX_POWER;
+    /// Get the red, green, and blue components of the color for this value, each 0 to 1.
+    fn rgb(&self, mut value: f64) -> (f64, f64, f64) {
+        if value.is_nan() {
+            value = self.max_value;
         }
 
-        power = power.min(MAX_POWER);
+        value = value.clamp(self.min_value, self.max_value);
 
-        if power <= FULL_RED_POWER {
-            gd = (FULL_RED_POWER - power) / FULL_RED_POWER * MAX_GREEN_FOR_ORANGE;
+        let rd = 1.0;
+        let gd;
+        let mut bd = 0.0;
+
+        if value <= self.full_red_value {
+            gd = (self.full_red_value - value) / (self.full_red_value - self.min_value)
+                * self.max_green_for_orange;
         } else {
-            gd = (power - FULL_RED_POWER) / (MAX_POWER - FULL_RED_POWER);
+            gd = (value - self.full_red_value) / (self.max_value - self.full_red_value);
             bd = gd;
         }
 
+        (rd, gd, bd)
+    }
+}
+
+impl PixelList {
+    fn kml_write_pixel_style<K: KmlWriter>(kml: &mut K, value: f64, ramp: &PixelColorRamp) {
+        let (rd, gd, bd) = ramp.rgb(value);
+        let ad = 0.6;
+
         let ri = (rd * 255.0) as i32;
         let gi = (gd * 255.0) as i32;
         let bi = (bd * 255.0) as i32;
@@ -674,8 +1546,26 @@ impl PixelList {
     /// that outputs a KML file where that higher function adds style information and the rest of the
     /// document.
     ///
-    pub fn kml_write<K: KmlWriter>(&self, kml: &mut K) {
-        for pixel in &self.0 {
+    /// The pixels are colored by their power using `ramp`, or the default ramp if it is `None`.
+    /// See [PixelList::kml_write_colored_by] to color by another value.
+    pub fn kml_write<K: KmlWriter>(&self, kml: &mut K, ramp: Option<&PixelColorRamp>) {
+        self.kml_write_colored_by(kml, ColorMode::Power, ramp)
+    }
+
+    /// Write out a pixel list in KML format with the pixels colored according to `mode`.
+    ///
+    /// If `ramp` is `None`, the default ramp for the mode is used. See [PixelList::kml_write] for
+    /// details.
+    pub fn kml_write_colored_by<K: KmlWriter>(
+        &self,
+        kml: &mut K,
+        mode: ColorMode,
+        ramp: Option<&PixelColorRamp>,
+    ) {
+        let default_ramp = mode.default_ramp();
+        let ramp = ramp.unwrap_or(&default_ramp);
+
+        for pixel in self {
             let mut desc: [u8; 256] = [0; 256];
             let mut cursor = std::io::Cursor::new(&mut desc[..]);
 
@@ -699,12 +1589,11 @@ impl PixelList {
             .unwrap();
 
             let position = cursor.position() as usize;
-            drop(cursor);
 
             let desc = unsafe { std::str::from_utf8_unchecked(&desc[..position]) };
             kml.start_placemark(None, Some(desc), None).unwrap();
 
-            Self::kml_write_pixel_style(kml, pixel.power);
+            Self::kml_write_pixel_style(kml, mode.value(pixel), ramp);
             kml.start_polygon(true, true, Some("clampToGround"))
                 .unwrap();
             kml.polygon_start_outer_ring().unwrap();
@@ -728,6 +1617,123 @@ impl PixelList {
             kml.finish_placemark().unwrap();
         }
     }
+
+    /// Write out the outline of the area covered by the pixels in KML format.
+    ///
+    /// Instead of drawing every pixel, this draws the polygons from [PixelList::dissolve_outline]
+    /// as a single placemark with a multigeometry of polygons. Holes are written as inner rings.
+    /// This is much smaller than the output of [PixelList::kml_write] for large clusters. The
+    /// outline is colored by the total power of the pixels using the default [PixelColorRamp].
+    pub fn kml_write_outline<K: KmlWriter>(&self, kml: &mut K, eps: f64) -> SatFireResult<()> {
+        let polygons = self.dissolve_outline(eps);
+        if polygons.is_empty() {
+            return Ok(());
+        }
+
+        kml.start_placemark(None, None, None)?;
+        Self::kml_write_pixel_style(kml, self.total_power(), &PixelColorRamp::default());
+        kml.start_multi_geometry()?;
+
+        for polygon in polygons {
+            kml.start_polygon(true, true, Some("clampToGround"))?;
+
+            kml.polygon_start_outer_ring()?;
+            Self::kml_write_outline_ring(kml, &polygon.outer)?;
+            kml.polygon_finish_outer_ring()?;
+
+            for hole in &polygon.holes {
+                kml.polygon_start_inner_ring()?;
+                Self::kml_write_outline_ring(kml, hole)?;
+                kml.polygon_finish_inner_ring()?;
+            }
+
+            kml.finish_polygon()?;
+        }
+
+        kml.finish_multi_geometry()?;
+        kml.finish_placemark()?;
+
+        Ok(())
+    }
+
+    /// Write a ring from an [OutlinePolygon] as a closed LinearRing.
+    fn kml_write_outline_ring<K: KmlWriter>(kml: &mut K, ring: &[Coord]) -> SatFireResult<()> {
+        kml.start_linear_ring()?;
+
+        // The outline rings are the reverse of the order KML expects, counter-clockwise outer
+        // rings and clockwise holes.
+        for coord in ring.iter().rev() {
+            kml.linear_ring_add_vertex(coord.lat, coord.lon, 0.0)?;
+        }
+
+        // Close the loop.
+        let last = ring[ring.len() - 1];
+        kml.linear_ring_add_vertex(last.lat, last.lon, 0.0)?;
+
+        kml.finish_linear_ring()
+    }
+}
+
+/*-------------------------------------------------------------------------------------------------
+ *                                         GeoJSON Export
+ *-----------------------------------------------------------------------------------------------*/
+
+impl PixelList {
+    /// Write out a pixel list as a GeoJSON FeatureCollection.
+    ///
+    /// Each pixel is a Polygon feature with the power, area, temperature, scan angle, mask flag, and
+    /// data quality flag as properties. Values that aren't finite numbers are written as null since
+    /// JSON has no representation for them.
+    pub fn to_geojson(&self) -> String {
+        use std::fmt::Write;
+
+        // Ignore write errors since we're writing to a String
+        let mut output = String::with_capacity(100 + 500 * self.0.len());
+
+        output.push_str(r#"{"type":"FeatureCollection","features":["#);
+
+        for (i, pixel) in self.0.iter().enumerate() {
+            if i > 0 {
+                output.push(',');
+            }
+
+            output.push_str(r#"{"type":"Feature","geometry":{"type":"Polygon","coordinates":[["#);
+            // GeoJSON is [lon, lat] and the first and last positions must be the same.
+            for (j, coord) in [pixel.ul, pixel.ll, pixel.lr, pixel.ur, pixel.ul]
+                .iter()
+                .enumerate()
+            {
+                if j > 0 {
+                    output.push(',');
+                }
+                let _ = write!(
+                    output,
+                    "[{},{}]",
+                    json_number(coord.lon),
+                    json_number(coord.lat)
+                );
+            }
+            output.push_str("]]},");
+
+            let _ = write!(
+                output,
+                concat!(
+                    r#""properties":{{"power":{},"area":{},"temperature":{},"#,
+                    r#""scan_angle":{},"mask_flag":{},"data_quality_flag":{}}}}}"#
+                ),
+                json_number(pixel.power),
+                json_number(pixel.area),
+                json_number(pixel.temperature),
+                json_number(pixel.scan_angle),
+                pixel.mask_flag.0,
+                pixel.data_quality_flag.0,
+            );
+        }
+
+        output.push_str("]}");
+
+        output
+    }
 }
 
 #[cfg(test)]
@@ -968,6 +1974,271 @@ mod test {
         assert!(pxl4.is_adjacent_to_or_overlaps(&pxl1, 1.0e-6));
     }
 
+    #[test]
+    #[rustfmt::skip]
+    fn test_satfire_pixels_overlap_area() {
+        let base = Pixel {
+            ul: Coord {lat: 0.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 0.0},
+            ur: Coord {lat: 0.0, lon: 0.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        let pxl1 = Pixel {
+            ul: Coord{lat: 45.0, lon: -120.0},
+            ll: Coord{lat: 44.0, lon: -120.0},
+            lr: Coord{lat: 44.0, lon: -119.0},
+            ur: Coord{lat: 45.0, lon: -119.0},
+            ..base
+        };
+
+        // Shifted half a pixel up and to the left of pxl1
+        let pxl2 = Pixel {
+            ul: Coord{lat: 45.5, lon: -120.5},
+            ll: Coord{lat: 44.5, lon: -120.5},
+            lr: Coord{lat: 44.5, lon: -119.5},
+            ur: Coord{lat: 45.5, lon: -119.5},
+            ..base
+        };
+
+        // Adjacent to pxl1 along its northern edge
+        let pxl3 = Pixel {
+            ul: Coord{lat: 46.0, lon: -120.0},
+            ll: Coord{lat: 45.0, lon: -120.0},
+            lr: Coord{lat: 45.0, lon: -119.0},
+            ur: Coord{lat: 46.0, lon: -119.0},
+            ..base
+        };
+
+        // The corners of pxl4 lie along the mid-points of pxl1, so it is entirely inside pxl1.
+        let pxl4 = Pixel {
+            ul: Coord{lat: 45.0, lon: -119.5},
+            ll: Coord{lat: 44.5, lon: -120.0},
+            lr: Coord{lat: 44.0, lon: -119.5},
+            ur: Coord{lat: 44.5, lon: -119.0},
+            ..base
+        };
+
+        // Far away from everything else
+        let pxl5 = Pixel {
+            ul: Coord{lat: 35.0, lon: -100.0},
+            ll: Coord{lat: 34.0, lon: -100.0},
+            lr: Coord{lat: 34.0, lon: -99.0},
+            ur: Coord{lat: 35.0, lon: -99.0},
+            ..base
+        };
+
+        assert!((pxl1.overlap_area(&pxl1, 1.0e-6) - 1.0).abs() < 1.0e-12);
+
+        assert!((pxl1.overlap_area(&pxl2, 1.0e-6) - 0.25).abs() < 1.0e-12);
+        assert!((pxl2.overlap_area(&pxl1, 1.0e-6) - 0.25).abs() < 1.0e-12);
+
+        assert!(pxl1.overlap_area(&pxl3, 1.0e-6).abs() < 1.0e-12);
+        assert!(pxl3.overlap_area(&pxl1, 1.0e-6).abs() < 1.0e-12);
+
+        assert!((pxl1.overlap_area(&pxl4, 1.0e-6) - 0.5).abs() < 1.0e-12);
+        assert!((pxl4.overlap_area(&pxl1, 1.0e-6) - 0.5).abs() < 1.0e-12);
+
+        assert_eq!(pxl1.overlap_area(&pxl5, 1.0e-6), 0.0);
+        assert_eq!(pxl5.overlap_area(&pxl1, 1.0e-6), 0.0);
+
+        assert!((pxl1.overlap_fraction(&pxl1, 1.0e-6) - 1.0).abs() < 1.0e-12);
+        assert!((pxl1.overlap_fraction(&pxl2, 1.0e-6) - 0.25).abs() < 1.0e-12);
+        assert!((pxl2.overlap_fraction(&pxl1, 1.0e-6) - 0.25).abs() < 1.0e-12);
+        assert!(pxl1.overlap_fraction(&pxl3, 1.0e-6).abs() < 1.0e-12);
+        assert!((pxl1.overlap_fraction(&pxl4, 1.0e-6) - 1.0).abs() < 1.0e-12);
+        assert!((pxl4.overlap_fraction(&pxl1, 1.0e-6) - 1.0).abs() < 1.0e-12);
+        assert_eq!(pxl1.overlap_fraction(&pxl5, 1.0e-6), 0.0);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_satfire_pixels_intersection() {
+        use crate::geo::polygon_signed_area;
+
+        let base = Pixel {
+            ul: Coord {lat: 0.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 0.0},
+            ur: Coord {lat: 0.0, lon: 0.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        let pxl1 = Pixel {
+            ul: Coord{lat: 45.0, lon: -120.0},
+            ll: Coord{lat: 44.0, lon: -120.0},
+            lr: Coord{lat: 44.0, lon: -119.0},
+            ur: Coord{lat: 45.0, lon: -119.0},
+            ..base
+        };
+
+        // Shifted half a pixel up and to the left of pxl1
+        let pxl2 = Pixel {
+            ul: Coord{lat: 45.5, lon: -120.5},
+            ll: Coord{lat: 44.5, lon: -120.5},
+            lr: Coord{lat: 44.5, lon: -119.5},
+            ur: Coord{lat: 45.5, lon: -119.5},
+            ..base
+        };
+
+        // Adjacent to pxl1 along its northern edge
+        let pxl3 = Pixel {
+            ul: Coord{lat: 46.0, lon: -120.0},
+            ll: Coord{lat: 45.0, lon: -120.0},
+            lr: Coord{lat: 45.0, lon: -119.0},
+            ur: Coord{lat: 46.0, lon: -119.0},
+            ..base
+        };
+
+        // Only touches pxl1 at its upper right corner
+        let pxl4 = Pixel {
+            ul: Coord{lat: 46.0, lon: -119.0},
+            ll: Coord{lat: 45.0, lon: -119.0},
+            lr: Coord{lat: 45.0, lon: -118.0},
+            ur: Coord{lat: 46.0, lon: -118.0},
+            ..base
+        };
+
+        let shared = pxl1.intersection(&pxl2, 1.0e-6).unwrap();
+        assert_eq!(shared.len(), 4);
+        assert!(polygon_signed_area(&shared) < 0.0);
+        assert!((polygon_signed_area(&shared) + 0.25).abs() < 1.0e-12);
+        for coord in &shared {
+            assert!(coord.lat >= 44.5 - 1.0e-12 && coord.lat <= 45.0 + 1.0e-12);
+            assert!(coord.lon >= -120.0 - 1.0e-12 && coord.lon <= -119.5 + 1.0e-12);
+        }
+
+        let shared = pxl2.intersection(&pxl1, 1.0e-6).unwrap();
+        assert_eq!(shared.len(), 4);
+        assert!(polygon_signed_area(&shared) < 0.0);
+
+        let shared = pxl1.intersection(&pxl1, 1.0e-6).unwrap();
+        assert_eq!(shared.len(), 4);
+        assert!((polygon_signed_area(&shared) + 1.0).abs() < 1.0e-12);
+
+        assert!(pxl1.intersection(&pxl3, 1.0e-6).is_none());
+        assert!(pxl3.intersection(&pxl1, 1.0e-6).is_none());
+        assert!(pxl1.intersection(&pxl4, 1.0e-6).is_none());
+        assert!(pxl4.intersection(&pxl1, 1.0e-6).is_none());
+
+        // Overlaps pxl1 by a sliver narrower than eps along its northern edge
+        let pxl5 = Pixel {
+            ul: Coord{lat: 46.0 - 5.0e-7, lon: -120.0},
+            ll: Coord{lat: 45.0 - 5.0e-7, lon: -120.0},
+            lr: Coord{lat: 45.0 - 5.0e-7, lon: -119.0},
+            ur: Coord{lat: 46.0 - 5.0e-7, lon: -119.0},
+            ..base
+        };
+        assert!(pxl1.intersection(&pxl5, 1.0e-6).is_none());
+        assert!(pxl5.intersection(&pxl1, 1.0e-6).is_none());
+        assert!(pxl1.intersection(&pxl5, 1.0e-8).is_some());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_geographic_area() {
+        use crate::geo::EARTH_RADIUS_M;
+
+        let base = Pixel {
+            ul: Coord {lat: 0.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 0.0},
+            ur: Coord {lat: 0.0, lon: 0.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        // A 1 degree by 1 degree box just north of the equator.
+        let pxl = Pixel {
+            ul: Coord {lat: 1.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 1.0},
+            ur: Coord {lat: 1.0, lon: 1.0},
+            ..base
+        };
+
+        // Area between two lines of latitude and two meridians on a sphere. The pixel's northern
+        // edge is a great circle arc instead of a line of latitude, but that difference is tiny
+        // this close to the equator.
+        let expected = EARTH_RADIUS_M * EARTH_RADIUS_M
+            * 1.0_f64.to_radians()
+            * (1.0_f64.to_radians().sin() - 0.0_f64.to_radians().sin());
+
+        let area = pxl.geographic_area_m2();
+        assert!(((area - expected) / expected).abs() < 1.0e-4, "{} vs {}", area, expected);
+
+        // Roughly 12,364 square kilometers
+        assert!((area / 1.0e6 - 12_364.0).abs() < 1.0);
+
+        // A degenerate pixel has no area.
+        assert_eq!(base.geographic_area_m2(), 0.0);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_exact_equality() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let pxl1 = Pixel {
+            ul: Coord{lat: 45.0, lon: -120.0},
+            ll: Coord{lat: 44.0, lon: -120.0},
+            lr: Coord{lat: 44.0, lon: -119.0},
+            ur: Coord{lat: 45.0, lon: -119.0},
+            power: 1.0,
+            area: 1.0,
+            temperature: 1.0,
+            scan_angle: 1.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        let pxl2 = Pixel {
+            ul: Coord{lat: 45.0000001, lon: -120.0},
+            ..pxl1
+        };
+
+        let pxl3 = Pixel {
+            mask_flag: MaskCode(10),
+            ..pxl1
+        };
+
+        assert_eq!(pxl1, pxl1);
+        assert_ne!(pxl1, pxl2);
+        assert_ne!(pxl1, pxl3);
+
+        // Not exactly equal, but approximately equal.
+        assert!(pxl1.approx_equal(&pxl2, 1.0e-6));
+
+        let hash_set: HashSet<Pixel> = [pxl1, pxl2, pxl3, pxl1, pxl2].into_iter().collect();
+        assert_eq!(hash_set.len(), 3);
+
+        let btree_set: BTreeSet<Pixel> = [pxl1, pxl2, pxl3, pxl1, pxl3].into_iter().collect();
+        assert_eq!(btree_set.len(), 3);
+
+        // Same corners but different values still hash to the same bucket, but aren't equal.
+        let pxl4 = Pixel {
+            power: 2.0,
+            ..pxl1
+        };
+        let hash_set: HashSet<Pixel> = [pxl1, pxl4, pxl1, pxl4].into_iter().collect();
+        assert_eq!(hash_set.len(), 2);
+    }
+
     #[test]
     #[rustfmt::skip]
     fn test_satfire_pixels_are_adjacent()
@@ -1337,6 +2608,70 @@ mod test {
         assert!(!sub_pxl_02.is_adjacent_to(&pxl_00, 1.0e-6));
     }
 
+    #[test]
+    #[rustfmt::skip]
+    fn test_satfire_pixels_adjacent_degenerate()
+    {
+        let base = Pixel {
+            ul: Coord {lat: 0.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 0.0},
+            ur: Coord {lat: 0.0, lon: 0.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        // Slivers thinner than eps, so each eastern corner of the western pixel is close to both
+        // western corners of the eastern pixel, giving 4 close pairs but only 2 close corners each.
+        let sliver_w = Pixel {
+            ul: Coord {lat: 45.0000001, lon: -121.0},
+            ll: Coord {lat: 45.0, lon: -121.0},
+            lr: Coord {lat: 45.0, lon: -120.0},
+            ur: Coord {lat: 45.0000001, lon: -120.0},
+            ..base
+        };
+
+        let sliver_e = Pixel {
+            ul: Coord {lat: 45.0000001, lon: -120.0},
+            ll: Coord {lat: 45.0, lon: -120.0},
+            lr: Coord {lat: 45.0, lon: -119.0},
+            ur: Coord {lat: 45.0000001, lon: -119.0},
+            ..base
+        };
+
+        assert!(sliver_w.is_adjacent_to(&sliver_e, 1.0e-6));
+        assert!(sliver_e.is_adjacent_to(&sliver_w, 1.0e-6));
+    }
+
+    /// A pixel aligned with the lat-lon grid with its lower left corner at (lat, lon).
+    fn square_pixel(lat: f64, lon: f64, size: f64) -> Pixel {
+        Pixel {
+            ul: Coord {
+                lat: lat + size,
+                lon,
+            },
+            ll: Coord { lat, lon },
+            lr: Coord {
+                lat,
+                lon: lon + size,
+            },
+            ur: Coord {
+                lat: lat + size,
+                lon: lon + size,
+            },
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        }
+    }
+
     #[rustfmt::skip]
     fn pixel_list_test_setup() -> PixelList {
 
@@ -1443,10 +2778,695 @@ mod test {
         let buf = plist.binary_serialize();
         let mut cursor = std::io::Cursor::new(buf);
 
-        let plist2 = PixelList::binary_deserialize(&mut cursor);
+        let plist2 = PixelList::binary_deserialize(&mut cursor).unwrap();
+        assert_eq!(plist2.0.len(), 9);
 
-        for (p1, p2) in plist.0.into_iter().zip(plist2.0.into_iter()) {
+        for (p1, p2) in plist.0.into_iter().zip(plist2.0) {
             assert!(p1.approx_equal(&p2, f64::MIN));
         }
     }
+
+    #[test]
+    fn satfire_pixel_list_test_binary_truncated() {
+        let plist = pixel_list_test_setup();
+
+        let buf = plist.binary_serialize();
+
+        // Chop off part of the last pixel
+        let mut cursor = std::io::Cursor::new(&buf[..buf.len() - 3]);
+        assert!(PixelList::binary_deserialize(&mut cursor).is_err());
+
+        // Not even enough bytes for the length
+        let mut cursor = std::io::Cursor::new(&buf[..3]);
+        assert!(PixelList::binary_deserialize(&mut cursor).is_err());
+
+        // An empty list is fine
+        let buf = PixelList::new().binary_serialize();
+        let mut cursor = std::io::Cursor::new(buf);
+        assert!(PixelList::binary_deserialize(&mut cursor)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn satfire_pixel_list_test_json_round_trip() {
+        let plist = pixel_list_test_setup();
+
+        let json = serde_json::to_string(&plist).unwrap();
+        assert!(json.starts_with("[{"));
+        let plist2: PixelList = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(plist.pixels(), plist2.pixels());
+    }
+
+    #[test]
+    fn satfire_pixel_list_test_kml_colored_by() {
+        use crate::KmlFile;
+
+        let mut plist = PixelList::new();
+        plist.push(Pixel {
+            ul: Coord {
+                lat: 45.0,
+                lon: -120.0,
+            },
+            ll: Coord {
+                lat: 44.0,
+                lon: -120.0,
+            },
+            lr: Coord {
+                lat: 44.0,
+                lon: -119.0,
+            },
+            ur: Coord {
+                lat: 45.0,
+                lon: -119.0,
+            },
+            power: 0.0,
+            area: 1_000_000.0,
+            temperature: 1_000.0,
+            scan_angle: 2.0,
+            mask_flag: MaskCode(10),
+            data_quality_flag: DataQualityFlagCode(0),
+        });
+
+        let kml_colors = |mode: ColorMode, ramp: Option<&PixelColorRamp>| -> String {
+            let mut buf: Vec<u8> = vec![];
+            {
+                let mut kml = KmlFile::from_writer(&mut buf).unwrap();
+                plist.kml_write_colored_by(&mut kml, mode, ramp);
+            }
+            String::from_utf8(buf).unwrap()
+        };
+
+        // Zero power is orange, max area is white, and the middle temperature is red.
+        assert!(kml_colors(ColorMode::Power, None).contains("<color>9900A4FF</color>"));
+        assert!(kml_colors(ColorMode::Area, None).contains("<color>99FFFFFF</color>"));
+        assert!(kml_colors(ColorMode::Temperature, None).contains("<color>990000FF</color>"));
+
+        // A custom ramp where this temperature is the maximum.
+        let ramp = PixelColorRamp::new(0.0, 500.0, 1_000.0).unwrap();
+        assert!(kml_colors(ColorMode::Temperature, Some(&ramp)).contains("<color>99FFFFFF</color>"));
+
+        // No green at the minimum makes zero power red instead of orange.
+        let ramp = ramp.with_max_green_for_orange(0.0).unwrap();
+        assert!(kml_colors(ColorMode::Power, Some(&ramp)).contains("<color>990000FF</color>"));
+    }
+
+    #[test]
+    fn test_pixel_color_ramp_validation() {
+        assert!(PixelColorRamp::new(0.0, 1.0, 2.0).is_ok());
+
+        // Out of order, or without room between the values, would divide by zero.
+        assert!(PixelColorRamp::new(0.0, 0.0, 2.0).is_err());
+        assert!(PixelColorRamp::new(0.0, 2.0, 2.0).is_err());
+        assert!(PixelColorRamp::new(2.0, 1.0, 0.0).is_err());
+
+        assert!(PixelColorRamp::new(f64::NAN, 1.0, 2.0).is_err());
+        assert!(PixelColorRamp::new(0.0, 1.0, f64::INFINITY).is_err());
+        assert!(PixelColorRamp::new(f64::NEG_INFINITY, 1.0, 2.0).is_err());
+
+        let ramp = PixelColorRamp::default();
+        assert!(ramp.with_max_green_for_orange(1.0).is_ok());
+        assert!(ramp.with_max_green_for_orange(-0.1).is_err());
+        assert!(ramp.with_max_green_for_orange(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn satfire_pixel_list_test_kml_write_outline() {
+        use crate::KmlFile;
+
+        let make_pixel = |lat: f64, lon: f64| Pixel {
+            power: 10.0,
+            ..square_pixel(lat, lon, 1.0)
+        };
+
+        let plist: PixelList = [
+            make_pixel(44.0, -120.0),
+            make_pixel(44.0, -119.0),
+            make_pixel(30.0, -100.0),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut buf: Vec<u8> = vec![];
+        {
+            let mut kml = KmlFile::from_writer(&mut buf).unwrap();
+            plist.kml_write_outline(&mut kml, 1.0e-6).unwrap();
+            PixelList::new()
+                .kml_write_outline(&mut kml, 1.0e-6)
+                .unwrap();
+        }
+        let contents = String::from_utf8(buf).unwrap();
+
+        assert_eq!(contents.matches("<Placemark>").count(), 1);
+        assert_eq!(contents.matches("<Polygon>").count(), 2);
+        assert!(contents.contains(concat!(
+            "<coordinates>\n",
+            "-120,45,0\n",
+            "-120,44,0\n",
+            "-118,44,0\n",
+            "-118,45,0\n",
+            "-120,45,0\n",
+            "</coordinates>"
+        )));
+    }
+
+    #[test]
+    fn satfire_pixel_list_test_geojson() {
+        let mut plist = PixelList::new();
+        plist.push(Pixel {
+            ul: Coord {
+                lat: 45.0,
+                lon: -120.0,
+            },
+            ll: Coord {
+                lat: 44.0,
+                lon: -120.0,
+            },
+            lr: Coord {
+                lat: 44.0,
+                lon: -119.0,
+            },
+            ur: Coord {
+                lat: 45.0,
+                lon: -119.0,
+            },
+            power: 12.5,
+            area: f64::NAN,
+            temperature: 400.0,
+            scan_angle: 2.0,
+            mask_flag: MaskCode(10),
+            data_quality_flag: DataQualityFlagCode(0),
+        });
+
+        let json = plist.to_geojson();
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+
+        assert!(json.starts_with(r#"{"type":"FeatureCollection","features":[{"#));
+        assert!(
+            json.contains(r#""coordinates":[[[-120,45],[-120,44],[-119,44],[-119,45],[-120,45]]]"#)
+        );
+        assert!(json.contains(r#""power":12.5,"area":null,"temperature":400,"#));
+        assert!(json.contains(r#""mask_flag":10,"data_quality_flag":0}"#));
+        assert!(json.ends_with("}]}"));
+
+        assert_eq!(
+            PixelList::new().to_geojson(),
+            r#"{"type":"FeatureCollection","features":[]}"#
+        );
+    }
+
+    #[test]
+    fn satfire_pixel_list_test_binary_versions() {
+        let plist = pixel_list_test_setup();
+
+        // Version 0 had no header, just the length and the pixels.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&plist.0.len().to_le_bytes());
+        for pixel in &plist.0 {
+            pixel.write_bytes(&mut buf).unwrap();
+        }
+
+        let mut cursor = std::io::Cursor::new(buf);
+        let plist2 = PixelList::binary_deserialize(&mut cursor).unwrap();
+        assert_eq!(plist.pixels(), plist2.pixels());
+
+        // Unknown versions are an error.
+        let mut buf = plist.binary_serialize();
+        assert_eq!(&buf[..4], b"SFPL");
+        buf[4] = 99;
+        let mut cursor = std::io::Cursor::new(buf);
+        assert!(PixelList::binary_deserialize(&mut cursor).is_err());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_list_dissolve_outline() {
+        use crate::geo::polygon_signed_area;
+
+        // A 2x2 block of pixels.
+        let mut plist = PixelList::new();
+        plist.push(square_pixel(44.0, -120.0, 1.0));
+        plist.push(square_pixel(44.0, -119.0, 1.0));
+        plist.push(square_pixel(45.0, -120.0, 1.0));
+        plist.push(square_pixel(45.0, -119.0, 1.0));
+
+        let outline = plist.dissolve_outline(1.0e-6);
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].outer.len(), 4);
+        assert!(outline[0].holes.is_empty());
+        assert!((polygon_signed_area(&outline[0].outer) + 4.0).abs() < 1.0e-12);
+
+        // Add an L shaped group of pixels away from the block.
+        plist.push(square_pixel(30.0, -100.0, 1.0));
+        plist.push(square_pixel(30.0, -99.0, 1.0));
+        plist.push(square_pixel(31.0, -100.0, 1.0));
+
+        let outline = plist.dissolve_outline(1.0e-6);
+        assert_eq!(outline.len(), 2);
+        assert_eq!(outline[0].outer.len(), 4);
+        assert_eq!(outline[1].outer.len(), 6);
+        assert!((polygon_signed_area(&outline[1].outer) + 3.0).abs() < 1.0e-12);
+
+        assert!(PixelList::new().dissolve_outline(1.0e-6).is_empty());
+    }
+
+    #[test]
+    fn test_pixel_list_dissolve_outline_hole() {
+        use crate::{geo::polygon_signed_area, KmlFile};
+
+        // A 3x3 block of pixels with the middle one missing.
+        let plist: PixelList = (0..3)
+            .flat_map(|j| (0..3).map(move |i| (j, i)))
+            .filter(|&(j, i)| (j, i) != (1, 1))
+            .map(|(j, i)| square_pixel(j as f64, i as f64, 1.0))
+            .collect();
+
+        let outline = plist.dissolve_outline(1.0e-6);
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].outer.len(), 4);
+        assert!((polygon_signed_area(&outline[0].outer) + 9.0).abs() < 1.0e-12);
+
+        assert_eq!(outline[0].holes.len(), 1);
+        let hole = &outline[0].holes[0];
+        assert_eq!(hole.len(), 4);
+        assert!((polygon_signed_area(hole) - 1.0).abs() < 1.0e-12);
+        assert!(hole
+            .iter()
+            .all(|coord| (1.0..=2.0).contains(&coord.lat) && (1.0..=2.0).contains(&coord.lon)));
+
+        let mut buf: Vec<u8> = vec![];
+        {
+            let mut kml = KmlFile::from_writer(&mut buf).unwrap();
+            plist.kml_write_outline(&mut kml, 1.0e-6).unwrap();
+        }
+        let contents = String::from_utf8(buf).unwrap();
+
+        assert_eq!(contents.matches("<Polygon>").count(), 1);
+        assert_eq!(contents.matches("<outerBoundaryIs>").count(), 1);
+        assert_eq!(contents.matches("<innerBoundaryIs>").count(), 1);
+        assert!(contents.contains(concat!(
+            "<innerBoundaryIs>\n",
+            "<LinearRing>\n",
+            "<coordinates>\n",
+            "2,1,0\n",
+            "1,1,0\n",
+            "1,2,0\n",
+            "2,2,0\n",
+            "2,1,0\n",
+            "</coordinates>\n",
+            "</LinearRing>\n",
+            "</innerBoundaryIs>"
+        )));
+    }
+
+    #[test]
+    fn test_pixel_list_dissolve_outline_large() {
+        // A solid block of pixels. This is too slow to be practical with a quadratic algorithm.
+        const SIZE: usize = 150;
+
+        let plist: PixelList = (0..SIZE)
+            .flat_map(|j| (0..SIZE).map(move |i| (j, i)))
+            .map(|(j, i)| square_pixel(40.0 + j as f64 * 0.01, -120.0 + i as f64 * 0.01, 0.01))
+            .collect();
+
+        let outline = plist.dissolve_outline(1.0e-6);
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].outer.len(), 4);
+        assert!(outline[0].holes.is_empty());
+    }
+
+    #[test]
+    fn test_pixel_list_contains_coord_and_nearest_pixel() {
+        let make_pixel = |lat: f64, lon: f64| Pixel {
+            power: lon,
+            ..square_pixel(lat, lon, 1.0)
+        };
+
+        // An L shape of pixels
+        let plist: PixelList = [
+            make_pixel(44.0, -120.0),
+            make_pixel(44.0, -119.0),
+            make_pixel(45.0, -120.0),
+        ]
+        .into_iter()
+        .collect();
+
+        let inside = Coord {
+            lat: 44.5,
+            lon: -118.5,
+        };
+        let in_the_notch = Coord {
+            lat: 45.5,
+            lon: -118.5,
+        };
+        let outside = Coord {
+            lat: 40.0,
+            lon: -118.5,
+        };
+
+        assert!(plist.contains_coord(inside, 1.0e-6));
+        assert!(!plist.contains_coord(in_the_notch, 1.0e-6));
+        assert!(!plist.contains_coord(outside, 1.0e-6));
+
+        let (pixel, dist) = plist.nearest_pixel(inside).unwrap();
+        assert_eq!(pixel.power, -119.0);
+        assert!(dist < 1.0);
+
+        let (pixel, dist) = plist.nearest_pixel(outside).unwrap();
+        assert_eq!(pixel.power, -119.0);
+        assert!(dist > 400_000.0);
+
+        assert!(PixelList::new().nearest_pixel(inside).is_none());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_list_into_iter() {
+        let base = Pixel {
+            ul: Coord {lat: 0.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 0.0},
+            ur: Coord {lat: 0.0, lon: 0.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        let mut plist = PixelList::new();
+        plist.push(Pixel { power: 1.0, ..base });
+        plist.push(Pixel { power: 2.0, ..base });
+        plist.push(Pixel { power: 3.0, ..base });
+
+        let mut total = 0.0;
+        for pixel in &plist {
+            total += pixel.power;
+        }
+        assert_eq!(total, 6.0);
+
+        let powers: Vec<f64> = plist.into_iter().map(|p| p.power).collect();
+        assert_eq!(powers, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_list_merge_with() {
+        let pxl1 = Pixel {
+            ul: Coord{lat: 45.0, lon: -120.0},
+            ll: Coord{lat: 44.0, lon: -120.0},
+            lr: Coord{lat: 44.0, lon: -119.0},
+            ur: Coord{lat: 45.0, lon: -119.0},
+            power: 10.0,
+            area: 1.0,
+            temperature: 400.0,
+            scan_angle: 1.0,
+            mask_flag: MaskCode(13),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        // Nearly identical to pxl1
+        let pxl2 = Pixel {
+            ul: Coord{lat: 45.001, lon: -120.001},
+            power: 20.0,
+            area: 2.0,
+            ..pxl1
+        };
+
+        // Adjacent to pxl1 along its northern edge
+        let pxl3 = Pixel {
+            ul: Coord{lat: 46.0, lon: -120.0},
+            ll: Coord{lat: 45.0, lon: -120.0},
+            lr: Coord{lat: 45.0, lon: -119.0},
+            ur: Coord{lat: 46.0, lon: -119.0},
+            ..pxl1
+        };
+
+        let other: PixelList = [pxl2, pxl3].into_iter().collect();
+
+        let mut plist: PixelList = [pxl1].into_iter().collect();
+        plist.merge_with(&other, 0.01, |p, o| {
+            p.power += o.power;
+            p.area += o.area;
+        });
+        assert_eq!(plist.len(), 2);
+        assert_eq!(plist[0].power, 30.0);
+        assert_eq!(plist[0].area, 3.0);
+
+        // With a tighter tolerance they don't match.
+        let mut plist: PixelList = [pxl1].into_iter().collect();
+        plist.merge_with(&other, 1.0e-6, |p, o| p.power += o.power);
+        assert_eq!(plist.len(), 3);
+        assert_eq!(plist[0].power, 10.0);
+
+        let mut plist: PixelList = [pxl1].into_iter().collect();
+        plist.max_merge(&other);
+        assert_eq!(plist.len(), 2);
+        assert_eq!(plist[0].power, 20.0);
+        assert_eq!(plist[0].area, 2.0);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_list_dedup_overlapping() {
+        let pxl1 = Pixel {
+            ul: Coord{lat: 45.0, lon: -120.0},
+            ll: Coord{lat: 44.0, lon: -120.0},
+            lr: Coord{lat: 44.0, lon: -119.0},
+            ur: Coord{lat: 45.0, lon: -119.0},
+            power: 10.0,
+            area: 1.0,
+            temperature: 400.0,
+            scan_angle: 1.0,
+            mask_flag: MaskCode(13),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        // Nearly identical to pxl1, as if seen in another scan sector.
+        let pxl2 = Pixel {
+            ul: Coord{lat: 45.01, lon: -120.01},
+            ll: Coord{lat: 44.01, lon: -120.01},
+            lr: Coord{lat: 44.01, lon: -119.01},
+            ur: Coord{lat: 45.01, lon: -119.01},
+            power: 20.0,
+            temperature: 350.0,
+            mask_flag: MaskCode(10),
+            ..pxl1
+        };
+
+        // Adjacent to pxl1 along its northern edge
+        let pxl3 = Pixel {
+            ul: Coord{lat: 46.0, lon: -120.0},
+            ll: Coord{lat: 45.0, lon: -120.0},
+            lr: Coord{lat: 45.0, lon: -119.0},
+            ur: Coord{lat: 46.0, lon: -119.0},
+            ..pxl1
+        };
+
+        let mut plist: PixelList = [pxl3, pxl2, pxl1].into_iter().collect();
+        plist.dedup_overlapping(1.0e-6);
+
+        assert_eq!(plist.len(), 2);
+        let merged = plist.pixels().iter().find(|p| p.power == 20.0).unwrap();
+        assert_eq!(merged.temperature, 400.0);
+        assert_eq!(merged.mask_flag, MaskCode(10));
+        assert!(plist.pixels().iter().any(|p| p.approx_equal(&pxl3, 1.0e-6)));
+    }
+
+    #[test]
+    fn test_pixel_list_adjacent_to_or_overlaps_matches_brute_force() {
+        // A simple linear congruential generator so the test is repeatable.
+        let mut state: u64 = 0x5EED;
+        let mut random = move || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        let mut pixel_list = |lat0: f64, lon0: f64, n: usize| -> PixelList {
+            (0..n)
+                .map(|_| {
+                    let i = (random() * 40.0).floor();
+                    let j = (random() * 40.0).floor();
+                    let (lat, lon) = (lat0 + 0.1 * j, lon0 + 0.1 * i);
+                    Pixel {
+                        ul: Coord {
+                            lat: lat + 0.1,
+                            lon,
+                        },
+                        ll: Coord { lat, lon },
+                        lr: Coord {
+                            lat,
+                            lon: lon + 0.1,
+                        },
+                        ur: Coord {
+                            lat: lat + 0.1,
+                            lon: lon + 0.1,
+                        },
+                        power: 1.0,
+                        area: 1.0,
+                        temperature: 1.0,
+                        scan_angle: 1.0,
+                        mask_flag: MaskCode(10),
+                        data_quality_flag: DataQualityFlagCode(0),
+                    }
+                })
+                .collect()
+        };
+
+        let mut num_adjacent = 0;
+        for trial in 0..40 {
+            // Sparse lists that only sometimes touch, with the offset list shifted around.
+            let n = 20 + trial * 5;
+            let offset = 0.1 * (trial % 8) as f64 + 2.0;
+            let a = pixel_list(0.0, 0.0, n);
+            let b = pixel_list(offset, offset, 30);
+
+            for (s, o) in [(&a, &b), (&b, &a)] {
+                let expected = s.adjacent_to_or_overlaps_brute_force(o, 1.0e-6);
+                assert_eq!(s.adjacent_to_or_overlaps(o, 1.0e-6), expected, "{}", trial);
+
+                if expected {
+                    num_adjacent += 1;
+                }
+            }
+        }
+
+        // Make sure both outcomes were tested.
+        assert!(num_adjacent > 0 && num_adjacent < 80, "{}", num_adjacent);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_list_optional_stats() {
+        let base = Pixel {
+            ul: Coord {lat: 0.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 0.0},
+            ur: Coord {lat: 0.0, lon: 0.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: f64::NAN,
+            scan_angle: f64::INFINITY,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        let mut plist = PixelList::new();
+        assert!(plist.centroid_opt().is_none());
+        assert!(plist.centroid().lat.is_nan());
+        assert_eq!(plist.max_temperature_opt(), None);
+        assert_eq!(plist.max_scan_angle_opt(), None);
+
+        plist.push(base);
+        assert_eq!(plist.max_temperature_opt(), None);
+        assert_eq!(plist.max_scan_angle_opt(), None);
+        #[allow(deprecated)]
+        let max_temperature = plist.maximum_temperature();
+        #[allow(deprecated)]
+        let max_scan_angle = plist.maximum_scan_angle();
+        assert_eq!(max_temperature, -f64::INFINITY);
+        assert_eq!(max_scan_angle, -f64::INFINITY);
+
+        plist.push(Pixel { temperature: 500.0, scan_angle: 3.0, ..base });
+        plist.push(Pixel { temperature: 700.0, scan_angle: 2.0, ..base });
+        assert_eq!(plist.centroid_opt().map(|c| c.lat), Some(0.0));
+        assert_eq!(plist.max_temperature_opt(), Some(700.0));
+        assert_eq!(plist.max_scan_angle_opt(), Some(3.0));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_list_bounding_box_cache() {
+        let pixel = |lat: f64, lon: f64| Pixel {
+            ul: Coord {lat: lat + 1.0, lon},
+            ll: Coord {lat, lon},
+            lr: Coord {lat, lon: lon + 1.0},
+            ur: Coord {lat: lat + 1.0, lon: lon + 1.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        let mut plist: PixelList = [pixel(0.0, 0.0)].into_iter().collect();
+        assert_eq!(plist.bounding_box().ur.lat, 1.0);
+
+        plist.push(pixel(5.0, 0.0));
+        assert_eq!(plist.bounding_box().ur.lat, 6.0);
+
+        plist.extend([pixel(0.0, 5.0)]);
+        assert_eq!(plist.bounding_box().ur.lon, 6.0);
+
+        plist.retain(|p| p.ll.lon < 1.0);
+        assert_eq!(plist.bounding_box().ur.lon, 1.0);
+
+        let other: PixelList = [pixel(-3.0, -3.0)].into_iter().collect();
+        plist.max_merge(&other);
+        assert_eq!(plist.bounding_box().ll.lat, -3.0);
+
+        plist.clear();
+        assert!(plist.bounding_box().ll.lat.is_infinite());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_pixel_list_collect_extend_and_index() {
+        let base = Pixel {
+            ul: Coord {lat: 0.0, lon: 0.0},
+            ll: Coord {lat: 0.0, lon: 0.0},
+            lr: Coord {lat: 0.0, lon: 0.0},
+            ur: Coord {lat: 0.0, lon: 0.0},
+            power: 0.0,
+            area: 0.0,
+            temperature: 0.0,
+            scan_angle: 0.0,
+            mask_flag: MaskCode(0),
+            data_quality_flag: DataQualityFlagCode(0),
+        };
+
+        let mut plist: PixelList = (1..=5)
+            .map(|i| Pixel { power: i as f64, ..base })
+            .filter(|p| p.power > 2.0)
+            .collect();
+        assert_eq!(plist.len(), 3);
+        assert_eq!(plist.total_power(), 12.0);
+        #[allow(deprecated)]
+        let total_are = plist.total_are();
+        assert_eq!(plist.total_area(), total_are);
+
+        plist.extend((6..=7).map(|i| Pixel { power: i as f64, ..base }));
+        assert_eq!(plist.len(), 5);
+        assert_eq!(plist.total_power(), 25.0);
+
+        assert_eq!(plist[0].power, 3.0);
+        assert_eq!(plist[4].power, 7.0);
+        assert_eq!(plist.get(1).map(|p| p.power), Some(4.0));
+        assert!(plist.get(5).is_none());
+
+        plist.retain(|p| p.power > 4.5);
+        assert_eq!(plist.len(), 3);
+        assert_eq!(plist[0].power, 5.0);
+
+        plist.push(Pixel { power: f64::NAN, temperature: 300.0, ..base });
+        plist.push(Pixel { power: 1.0, temperature: f64::INFINITY, ..base });
+        plist.push(Pixel { power: f64::INFINITY, temperature: f64::NAN, ..base });
+        plist.push(Pixel { power: 9.0, temperature: 400.0, ..base });
+
+        plist.sort_by_power_desc();
+        let powers: Vec<f64> = plist.pixels().iter().map(|p| p.power).collect();
+        assert_eq!(&powers[..5], &[9.0, 7.0, 6.0, 5.0, 1.0]);
+        assert!(powers[5..].iter().all(|p| !p.is_finite()));
+
+        plist.sort_by_temperature_desc();
+        assert_eq!(plist[0].temperature, 400.0);
+        assert_eq!(plist[1].temperature, 300.0);
+        assert!(plist.pixels()[5..].iter().all(|p| !p.temperature.is_finite()));
+    }
 }
@@ -1,15 +1,20 @@
 /*! Contains all the information about satellites. */
 
-use chrono::{DateTime, NaiveDate, Utc};
-use strum::{Display, EnumIter};
+use crate::SatFireError;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::str::FromStr;
+use strum::{Display, EnumIter, IntoEnumIterator};
 
 /** The GOES satellites this library works with. */
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, Display)]
 pub enum Satellite {
     /// GOES-16 (formerly GOES-R), or commonly known as GOES East
     G16,
-    /// GOES-17 (formerly GOES-S), or commonly known as GOES West
+    /// GOES-17 (formerly GOES-S), GOES West until it was replaced by GOES-18 in 2023
     G17,
+    /// GOES-18 (formerly GOES-T), or commonly known as GOES West
+    G18,
 }
 
 impl Satellite {
@@ -20,16 +25,30 @@ impl Satellite {
         match self {
             G16 => "G16",
             G17 => "G17",
+            G18 => "G18",
+        }
+    }
+
+    /// Get the long form of the satellite name, e.g. "GOES-16".
+    pub fn long_name(&self) -> &'static str {
+        use Satellite::*;
+
+        match self {
+            G16 => "GOES-16",
+            G17 => "GOES-17",
+            G18 => "GOES-18",
         }
     }
 
     /// Scan the string for the occurence of a satellite name.
+    ///
+    /// Both the short ("G16") and long ("GOES-16") forms of the name are recognized.
     pub fn string_contains_satellite(string: &str) -> Option<Satellite> {
         use Satellite::*;
 
-        let all_sats = [G16, G17];
+        let all_sats = [G16, G17, G18];
         for sat in &all_sats {
-            if string.contains(sat.name()) {
+            if string.contains(sat.name()) || string.contains(sat.long_name()) {
                 return Some(*sat);
             }
         }
@@ -48,20 +67,66 @@ impl Satellite {
         match self {
             G16 => DateTime::from_utc(NaiveDate::from_ymd(2017, 12, 18).and_hms(12, 0, 0), Utc),
             G17 => DateTime::from_utc(NaiveDate::from_ymd(2019, 2, 12).and_hms(12, 0, 0), Utc),
+            G18 => DateTime::from_utc(NaiveDate::from_ymd(2023, 1, 4).and_hms(12, 0, 0), Utc),
         }
     }
+
+    /// Get the earliest operational date of all the satellites this library works with.
+    pub fn earliest_operational() -> DateTime<Utc> {
+        Satellite::iter()
+            .map(|sat| sat.operational())
+            .min()
+            .expect("there is at least one satellite")
+    }
+
+    /// Get the longitude of the satellite's nadir point in degrees while in its operational
+    /// position.
+    ///
+    /// GOES-16 is GOES East at 75.2 W. GOES-17 was GOES West at 137.2 W, and GOES-18 replaced it
+    /// as GOES West at 137.0 W.
+    pub fn nadir_longitude(&self) -> f64 {
+        use Satellite::*;
+
+        match self {
+            G16 => -75.2,
+            G17 => -137.2,
+            G18 => -137.0,
+        }
+    }
+}
+
+impl FromStr for Satellite {
+    type Err = SatFireError;
+
+    /// Parse the exact short name of a satellite, e.g. "G16".
+    ///
+    /// Unlike `string_contains_satellite`, this does not search for the name inside a longer
+    /// string, so it is suitable for parsing user input.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Satellite::iter()
+            .find(|sat| sat.name() == s)
+            .ok_or_else(|| SatFireError::Parse(format!("not a valid satellite name: {}", s)))
+    }
 }
 
-/** The satellite scan sectors this library recognizes. */
+/** The satellite scan sectors this library recognizes.
+ *
+ * With the serde feature these are serialized using their file name abbreviation, e.g. "FDCF".
+ */
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, Display)]
 pub enum Sector {
     /// This is the full disk sector that includes the full viewable disk of the Earth.
+    #[cfg_attr(feature = "serde", serde(rename = "FDCF"))]
     FULL,
     /// The Continental U.S. sector, which actually includes much of the U.S on both satellites.
+    #[cfg_attr(feature = "serde", serde(rename = "FDCC"))]
     CONUS,
     /// Meso-sector 1, a floating sector with 1 minute imagery updates.
+    #[cfg_attr(feature = "serde", serde(rename = "FDCM1"))]
     MESO1,
     /// Meso-sector 2, a floating sector with 1 minute imagery updates.
+    #[cfg_attr(feature = "serde", serde(rename = "FDCM2"))]
     MESO2,
 }
 
@@ -81,6 +146,21 @@ impl Sector {
         }
     }
 
+    /// Get the nominal time between the start of consecutive scans of this sector.
+    ///
+    /// These are the values for the ABI scan mode 6, which has been the default since April of
+    /// 2019. Under the older mode 3 the full disk was only scanned every 15 minutes, so this may
+    /// not match every historical period or times when the satellite was in a special scan mode.
+    pub fn nominal_scan_interval(&self) -> Duration {
+        use Sector::*;
+
+        match self {
+            FULL => Duration::minutes(10),
+            CONUS => Duration::minutes(5),
+            MESO1 | MESO2 => Duration::minutes(1),
+        }
+    }
+
     /// Scan the string for the occurrence of a sector name and return first one found.
     ///
     /// Note that in some cases either of the meso-sectors can be represented by "FDCM", such as in
@@ -105,6 +185,20 @@ impl Sector {
     }
 }
 
+impl FromStr for Sector {
+    type Err = SatFireError;
+
+    /// Parse the exact name of a sector as used in the file names, e.g. "FDCF".
+    ///
+    /// Unlike `string_contains_sector`, this does not search for the name inside a longer string
+    /// and does not accept the ambiguous "FDCM", so it is suitable for parsing user input.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sector::iter()
+            .find(|sector| sector.name() == s)
+            .ok_or_else(|| SatFireError::Parse(format!("not a valid sector name: {}", s)))
+    }
+}
+
 /// Parse the satellite, sector, scan start time, and scan end time from a file name
 pub fn parse_satellite_description_from_file_name(
     fname: &str,
@@ -119,7 +213,8 @@ pub fn parse_satellite_description_from_file_name(
 }
 
 /// Represents a code from the Mask field of the NetCDF files.
-#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct MaskCode(pub i16);
 
 impl MaskCode {
@@ -181,6 +276,81 @@ impl MaskCode {
             _ => "unknown code",
         }
     }
+
+    /// Iterate over all the documented mask codes.
+    pub fn all() -> impl Iterator<Item = MaskCode> {
+        const ALL_CODES: [i16; 46] = [
+            -99, 0, 10, 11, 12, 13, 14, 15, 30, 31, 32, 33, 34, 35, 40, 50, 60, 100, 120, 121, 123,
+            124, 125, 126, 127, 150, 151, 152, 153, 170, 180, 182, 185, 186, 187, 188, 200, 201,
+            205, 210, 215, 220, 225, 230, 240, 245,
+        ];
+
+        ALL_CODES.into_iter().map(MaskCode)
+    }
+
+    /// Get the general category of this mask code.
+    pub fn category(self) -> MaskCategory {
+        use MaskCategory::*;
+
+        match self.0 {
+            -99 => Missing,
+            0 => Unprocessed,
+            10..=15 => Fire,
+            30..=35 => TemporallyFilteredFire,
+            40 => OffEarth,
+            50 | 60 => BlockOutZone,
+            100 => NoFire,
+            120 | 121 | 123..=127 => BadInput,
+            150..=153 => InvalidEcosystem,
+            170 | 180 | 182 | 185..=188 => AlgorithmFailure,
+            200 | 201 | 205 | 210 | 215 | 220 | 225 | 230 | 240 | 245 => Cloud,
+            _ => Unknown,
+        }
+    }
+
+    /// Is this a fire pixel with at least a medium probability of being a fire?
+    ///
+    /// This includes the temporally filtered fire pixels, but NOT the low probability fire pixels
+    /// (codes 15 and 35).
+    pub fn is_fire(self) -> bool {
+        matches!(self.0, 10..=14 | 30..=34)
+    }
+
+    /// Is this a fire pixel that was detected with the help of temporal filtering?
+    pub fn is_temporally_filtered(self) -> bool {
+        self.category() == MaskCategory::TemporallyFilteredFire
+    }
+}
+
+/// General categories of the codes in the Mask field of the NetCDF files.
+///
+/// See [MaskCode::as_str] for the individual codes that make up each category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaskCategory {
+    /// Missing data.
+    Missing,
+    /// The pixel was not processed.
+    Unprocessed,
+    /// A fire pixel, codes 10 through 15.
+    Fire,
+    /// A fire pixel detected with the help of temporal filtering, codes 30 through 35.
+    TemporallyFilteredFire,
+    /// The pixel is not on the Earth's disk.
+    OffEarth,
+    /// The pixel is in a local zenith angle, solar zenith angle, or glint angle block out zone.
+    BlockOutZone,
+    /// The pixel was processed and no fire was detected.
+    NoFire,
+    /// The input radiance data was missing, saturated, invalid, or below a threshold.
+    BadInput,
+    /// The ecosystem type, usually water or desert, is not valid for fire detection.
+    InvalidEcosystem,
+    /// The background or one of the fire characteristics could not be computed.
+    AlgorithmFailure,
+    /// The pixel was flagged as cloud or cloud edge.
+    Cloud,
+    /// The code is not one of the documented codes.
+    Unknown,
 }
 
 /// Represents a code from the DQF (Data Quality Flag) field of the NetCDF file.
@@ -190,7 +360,8 @@ impl MaskCode {
 /// [GOES-R SERIES PRODUCT DEFINITION AND USERS’ GUIDE][doc_url] retrieved December 10th, 2021.
 ///
 /// [doc_url]: (https://www.goes-r.gov/products/docs/PUG-L2+-vol5.pdf)
-#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct DataQualityFlagCode(pub i16);
 
 impl DataQualityFlagCode {
@@ -207,4 +378,224 @@ impl DataQualityFlagCode {
             _ => "unknown",
         }
     }
+
+    /// Iterate over all the documented DQF codes.
+    pub fn all() -> impl Iterator<Item = DataQualityFlagCode> {
+        [0, 1, 2, 3, 4, 5, 255].into_iter().map(DataQualityFlagCode)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_string_contains_satellite() {
+        use Satellite::*;
+
+        const G18_FILE: &str =
+            "OR_ABI-L2-FDCF-M6_G18_s20232130100319_e20232130109386_c20232130109511.nc.zip";
+
+        assert_eq!(Satellite::string_contains_satellite(G18_FILE), Some(G18));
+        assert_eq!(Satellite::string_contains_satellite("GOES-18"), Some(G18));
+        assert_eq!(Satellite::string_contains_satellite("GOES-16"), Some(G16));
+        assert_eq!(
+            Satellite::string_contains_satellite("G17/ABI-L2-FDCF"),
+            Some(G17)
+        );
+        assert_eq!(Satellite::string_contains_satellite("GOES-15"), None);
+    }
+
+    #[test]
+    fn test_from_str() {
+        for sat in Satellite::iter() {
+            assert_eq!(sat.name().parse::<Satellite>().unwrap(), sat);
+        }
+
+        for sector in Sector::iter() {
+            assert_eq!(sector.name().parse::<Sector>().unwrap(), sector);
+        }
+
+        assert!("GOES-16".parse::<Satellite>().is_err());
+        assert!("G16/ABI-L2-FDCF".parse::<Satellite>().is_err());
+        assert!("g16".parse::<Satellite>().is_err());
+        assert!("".parse::<Satellite>().is_err());
+
+        assert!("FDCM".parse::<Sector>().is_err());
+        assert!("FULL".parse::<Sector>().is_err());
+        assert!("ABI-L2-FDCF".parse::<Sector>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        for sat in Satellite::iter() {
+            let json = serde_json::to_string(&sat).unwrap();
+            assert_eq!(json, format!("\"{}\"", sat.name()));
+            assert_eq!(serde_json::from_str::<Satellite>(&json).unwrap(), sat);
+        }
+
+        for sector in Sector::iter() {
+            let json = serde_json::to_string(&sector).unwrap();
+            assert_eq!(json, format!("\"{}\"", sector.name()));
+            assert_eq!(serde_json::from_str::<Sector>(&json).unwrap(), sector);
+        }
+    }
+
+    #[test]
+    fn test_operational_dates() {
+        use Satellite::*;
+
+        let date = |year, month, day| {
+            DateTime::<Utc>::from_utc(NaiveDate::from_ymd(year, month, day).and_hms(12, 0, 0), Utc)
+        };
+
+        assert_eq!(G16.operational(), date(2017, 12, 18));
+        assert_eq!(G17.operational(), date(2019, 2, 12));
+        assert_eq!(G18.operational(), date(2023, 1, 4));
+
+        assert_eq!(Satellite::earliest_operational(), G16.operational());
+    }
+
+    #[test]
+    fn test_nadir_longitude() {
+        use Satellite::*;
+
+        assert_eq!(G16.nadir_longitude(), -75.2);
+        assert_eq!(G17.nadir_longitude(), -137.2);
+        assert_eq!(G18.nadir_longitude(), -137.0);
+    }
+
+    #[test]
+    fn test_nominal_scan_interval() {
+        assert_eq!(Sector::FULL.nominal_scan_interval(), Duration::minutes(10));
+        assert_eq!(Sector::CONUS.nominal_scan_interval(), Duration::minutes(5));
+        assert_eq!(Sector::MESO1.nominal_scan_interval(), Duration::minutes(1));
+        assert_eq!(Sector::MESO2.nominal_scan_interval(), Duration::minutes(1));
+    }
+
+    #[test]
+    fn test_mask_code_categories() {
+        use MaskCategory::*;
+
+        #[rustfmt::skip]
+        let cases: &[(i16, MaskCategory, bool, bool)] = &[
+            // code, category, is_fire, is_temporally_filtered
+            (-99, Missing,                false, false),
+            (  0, Unprocessed,            false, false),
+            ( 10, Fire,                   true,  false),
+            ( 11, Fire,                   true,  false),
+            ( 12, Fire,                   true,  false),
+            ( 13, Fire,                   true,  false),
+            ( 14, Fire,                   true,  false),
+            ( 15, Fire,                   false, false),
+            ( 30, TemporallyFilteredFire, true,  true),
+            ( 31, TemporallyFilteredFire, true,  true),
+            ( 32, TemporallyFilteredFire, true,  true),
+            ( 33, TemporallyFilteredFire, true,  true),
+            ( 34, TemporallyFilteredFire, true,  true),
+            ( 35, TemporallyFilteredFire, false, true),
+            ( 40, OffEarth,               false, false),
+            ( 50, BlockOutZone,           false, false),
+            ( 60, BlockOutZone,           false, false),
+            (100, NoFire,                 false, false),
+            (120, BadInput,               false, false),
+            (121, BadInput,               false, false),
+            (123, BadInput,               false, false),
+            (124, BadInput,               false, false),
+            (125, BadInput,               false, false),
+            (126, BadInput,               false, false),
+            (127, BadInput,               false, false),
+            (150, InvalidEcosystem,       false, false),
+            (151, InvalidEcosystem,       false, false),
+            (152, InvalidEcosystem,       false, false),
+            (153, InvalidEcosystem,       false, false),
+            (170, AlgorithmFailure,       false, false),
+            (180, AlgorithmFailure,       false, false),
+            (182, AlgorithmFailure,       false, false),
+            (185, AlgorithmFailure,       false, false),
+            (186, AlgorithmFailure,       false, false),
+            (187, AlgorithmFailure,       false, false),
+            (188, AlgorithmFailure,       false, false),
+            (200, Cloud,                  false, false),
+            (201, Cloud,                  false, false),
+            (205, Cloud,                  false, false),
+            (210, Cloud,                  false, false),
+            (215, Cloud,                  false, false),
+            (220, Cloud,                  false, false),
+            (225, Cloud,                  false, false),
+            (230, Cloud,                  false, false),
+            (240, Cloud,                  false, false),
+            (245, Cloud,                  false, false),
+            (  1, Unknown,                false, false),
+            (122, Unknown,                false, false),
+            (171, Unknown,                false, false),
+            (181, Unknown,                false, false),
+            (184, Unknown,                false, false),
+            (202, Unknown,                false, false),
+            (255, Unknown,                false, false),
+        ];
+
+        for &(code, category, is_fire, is_temporally_filtered) in cases {
+            let code = MaskCode(code);
+            assert_eq!(code.category(), category, "{}", code.0);
+            assert_eq!(code.is_fire(), is_fire, "{}", code.0);
+            assert_eq!(
+                code.is_temporally_filtered(),
+                is_temporally_filtered,
+                "{}",
+                code.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_all_codes_are_documented() {
+        assert_eq!(MaskCode::all().count(), 46);
+        for code in MaskCode::all() {
+            assert_ne!(code.as_str(), "unknown code", "{}", code.0);
+            assert_ne!(code.category(), MaskCategory::Unknown, "{}", code.0);
+        }
+
+        // Only the documented codes get a category.
+        for code in (-128..=300).map(MaskCode) {
+            let documented = code.as_str() != "unknown code";
+            assert_eq!(
+                code.category() != MaskCategory::Unknown,
+                documented,
+                "{}",
+                code.0
+            );
+        }
+
+        assert_eq!(DataQualityFlagCode::all().count(), 7);
+        for code in DataQualityFlagCode::all() {
+            assert_ne!(code.as_str(), "unknown", "{}", code.0);
+        }
+    }
+
+    #[test]
+    fn test_string_contains_sector_meso() {
+        use Sector::*;
+
+        const M1_FILE: &str =
+            "OR_ABI-L2-FDCM1-M6_G17_s20212130100319_e20212130100376_c20212130100511.nc";
+        const M2_FILE: &str =
+            "OR_ABI-L2-FDCM2-M6_G17_s20212130100319_e20212130100376_c20212130100511.nc";
+
+        assert_eq!(Sector::string_contains_sector(M1_FILE), Some(MESO1));
+        assert_eq!(Sector::string_contains_sector(M2_FILE), Some(MESO2));
+        assert_eq!(
+            Sector::string_contains_sector("G17/ABI-L2-FDCM/2021"),
+            Some(MESO1)
+        );
+        assert_eq!(
+            Sector::string_contains_sector("G17/ABI-L2-FDCF/2021"),
+            Some(FULL)
+        );
+        assert_eq!(
+            Sector::string_contains_sector("G17/ABI-L2-FDCC/2021"),
+            Some(CONUS)
+        );
+    }
 }